@@ -0,0 +1,16 @@
+// `v.push(v.len())` is accepted thanks to two-phase borrows: the
+// mutable borrow of `v` is only reserved while `v.len()` reads `v`, and
+// activated by the call to `push`.
+fn push_len(mut v: Vec<usize>) {
+    v.push(v.len());
+}
+
+// Mutating `v` while the two-phase borrow is reserved is an error.
+fn clear_in_argument(mut v: Vec<usize>) {
+    v.push({
+        v.clear();
+        0
+    });
+}
+
+fn main() {}
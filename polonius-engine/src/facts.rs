@@ -1,8 +1,8 @@
 use std::hash::Hash;
 
 /// The "facts" which are the basis of the NLL borrow analysis.
-#[derive(Clone)]
-pub struct AllFacts<R: Atom, L: Atom, P: Atom> {
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AllFacts<R: Atom, L: Atom, P: Atom, V: Atom, M: Atom> {
     /// `borrow_region(R, B, P)` -- the region R may refer to data
     /// from borrow B starting at the point P (this is usually the
     /// point *after* a borrow rvalue)
@@ -24,10 +24,57 @@ pub struct AllFacts<R: Atom, L: Atom, P: Atom> {
     pub region_live_at: Vec<(R, P)>,
 
     ///  `invalidates(P, L)` when the loan L is invalidated at point P
-    pub invalidates: Vec<(P, L)>
+    pub invalidates: Vec<(P, L)>,
+
+    /// `var_defined(V, P)` when the variable V is overwritten at point P
+    pub var_defined: Vec<(V, P)>,
+
+    /// `var_used(V, P)` when the variable V is used for anything but a drop at point P
+    pub var_used: Vec<(V, P)>,
+
+    /// `var_drop_used(V, P)` when the variable V is dropped at point P
+    pub var_drop_used: Vec<(V, P)>,
+
+    /// `var_uses_region(V, R)` when the type of V includes the region R
+    pub var_uses_region: Vec<(V, R)>,
+
+    /// `var_drops_region(V, R)` when dropping V uses the region R
+    pub var_drops_region: Vec<(V, R)>,
+
+    /// `child(M1, M2)` when the move path M1 is a direct child of M2, like `a.b` of `a`
+    pub child: Vec<(M, M)>,
+
+    /// `path_assigned_at(M, P)` when the move path M is initialized at point P;
+    /// the paths not assigned at the entry of the CFG, as the function
+    /// arguments should be, start out uninitialized
+    pub path_assigned_at: Vec<(M, P)>,
+
+    /// `path_moved_at(M, P)` when the move path M is moved out of at point P
+    pub path_moved_at: Vec<(M, P)>,
+
+    /// `path_accessed_at(M, P)` when the move path M is used at point P
+    pub path_accessed_at: Vec<(M, P)>,
+
+    /// `loan_path(B, M)` when the loan B borrows the move path M; see `derive_killed`
+    pub loan_path: Vec<(L, M)>,
+
+    /// `loan_reserved_at(B, P)` when the two-phase borrow B is reserved at point P
+    pub loan_reserved_at: Vec<(L, P)>,
+
+    /// `loan_activated_at(B, P)` when the two-phase borrow B is activated at point P
+    pub loan_activated_at: Vec<(L, P)>,
+
+    /// `invalidates_by_read(P, B)` when the `invalidates(P, B)` fact is due to
+    /// a read, which doesn't conflict with a two-phase borrow B before its activation
+    pub invalidates_by_read: Vec<(P, L)>,
+
+    /// `invalidates_by_access(P, B, A, K)` when the access of kind A at point P
+    /// reaches the path borrowed by the loan B of kind K: it invalidates B if
+    /// `A.conflicts_with(K)`, in addition to the `invalidates` facts
+    pub invalidates_by_access: Vec<(P, L, AccessKind, LoanKind)>,
 }
 
-impl<R: Atom, L: Atom, P: Atom> Default for AllFacts<R, L, P> {
+impl<R: Atom, L: Atom, P: Atom, V: Atom, M: Atom> Default for AllFacts<R, L, P, V, M> {
     fn default() -> Self {
         AllFacts {
             borrow_region: Vec::default(),
@@ -37,6 +84,20 @@ impl<R: Atom, L: Atom, P: Atom> Default for AllFacts<R, L, P> {
             outlives: Vec::default(),
             region_live_at: Vec::default(),
             invalidates: Vec::default(),
+            var_defined: Vec::default(),
+            var_used: Vec::default(),
+            var_drop_used: Vec::default(),
+            var_uses_region: Vec::default(),
+            var_drops_region: Vec::default(),
+            child: Vec::default(),
+            path_assigned_at: Vec::default(),
+            path_moved_at: Vec::default(),
+            path_accessed_at: Vec::default(),
+            loan_path: Vec::default(),
+            loan_reserved_at: Vec::default(),
+            loan_activated_at: Vec::default(),
+            invalidates_by_read: Vec::default(),
+            invalidates_by_access: Vec::default(),
         }
     }
 }
@@ -44,3 +105,91 @@ impl<R: Atom, L: Atom, P: Atom> Default for AllFacts<R, L, P> {
 pub trait Atom: From<usize> + Into<usize> + Copy + Clone + Eq + Ord + Hash + 'static {
     fn index(self) -> usize;
 }
+
+/// How a path is accessed, in `invalidates_by_access`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum AccessKind {
+    /// Reading the path, e.g. copying it or borrowing it immutably.
+    Read,
+
+    /// Overwriting the path itself, e.g. assigning it: this doesn't reach
+    /// the data behind references it contains.
+    ShallowWrite,
+
+    /// Writing the path and everything it owns, e.g. moving out of it or
+    /// borrowing it mutably.
+    DeepWrite,
+
+    /// The path goes out of scope, at a `StorageDead` or a drop.
+    StorageDead,
+}
+
+/// The kind of a loan, in `invalidates_by_access`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LoanKind {
+    /// `&place`
+    Shared,
+
+    /// The borrows rustc adds to keep match guards from changing the
+    /// matched place: they only forbid writes.
+    Shallow,
+
+    /// `&mut place`
+    Mutable,
+}
+
+impl AccessKind {
+    /// Whether an access of this kind invalidates a loan of kind `loan`:
+    /// reads only conflict with mutable loans, and writes with all loans.
+    pub fn conflicts_with(self, loan: LoanKind) -> bool {
+        self != AccessKind::Read || loan == LoanKind::Mutable
+    }
+
+    /// The name of the variant, which `from_str` parses back.
+    pub fn name(self) -> &'static str {
+        match self {
+            AccessKind::Read => "Read",
+            AccessKind::ShallowWrite => "ShallowWrite",
+            AccessKind::DeepWrite => "DeepWrite",
+            AccessKind::StorageDead => "StorageDead",
+        }
+    }
+}
+
+impl LoanKind {
+    /// The name of the variant, which `from_str` parses back.
+    pub fn name(self) -> &'static str {
+        match self {
+            LoanKind::Shared => "Shared",
+            LoanKind::Shallow => "Shallow",
+            LoanKind::Mutable => "Mutable",
+        }
+    }
+}
+
+impl ::std::str::FromStr for AccessKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_ref() {
+            "read" => Ok(AccessKind::Read),
+            "shallowwrite" => Ok(AccessKind::ShallowWrite),
+            "deepwrite" => Ok(AccessKind::DeepWrite),
+            "storagedead" => Ok(AccessKind::StorageDead),
+            _ => Err(String::from(
+                "valid values: Read, ShallowWrite, DeepWrite, StorageDead",
+            )),
+        }
+    }
+}
+
+impl ::std::str::FromStr for LoanKind {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_ref() {
+            "shared" => Ok(LoanKind::Shared),
+            "shallow" => Ok(LoanKind::Shallow),
+            "mutable" => Ok(LoanKind::Mutable),
+            _ => Err(String::from("valid values: Shared, Shallow, Mutable")),
+        }
+    }
+}
@@ -1,8 +1,17 @@
-/// Contains the core of the Polonius borrow checking engine.
-/// Input is fed in via AllFacts, and outputs are returned via Output
+//! Contains the core of the Polonius borrow checking engine.
+//! Input is fed in via AllFacts, and outputs are returned via Output
+
+extern crate datafrog;
+extern crate fxhash;
 
 mod facts;
+mod output;
 
 // Reexports of facts
 pub use facts::Atom;
+pub use facts::{AccessKind, LoanKind};
 pub use facts::AllFacts;
+pub use output::derive_killed;
+pub use output::Algorithm;
+pub use output::Output;
+pub use output::{Profile, RuleProfile, VariableProfile};
@@ -0,0 +1,60 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Decides which of the `invalidates_by_access` facts are conflicts,
+//! depending on the kinds of the access and of the loan.
+
+use fxhash::FxHashMap;
+use std::collections::BTreeSet;
+
+use facts::{AccessKind, AllFacts, Atom, LoanKind};
+
+/// Adds to `invalidates` the accesses conflicting with the loans they reach.
+pub(super) fn add_conflicting_accesses<Region, Loan, Point, Variable, Path>(
+    all_facts: &mut AllFacts<Region, Loan, Point, Variable, Path>,
+) where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    Variable: Atom,
+    Path: Atom,
+{
+    let conflicts: Vec<_> = all_facts
+        .invalidates_by_access
+        .iter()
+        .filter(|&&(_, _, access, loan)| access.conflicts_with(loan))
+        .map(|&(p, b, _, _)| (p, b))
+        .collect();
+    all_facts.invalidates.extend(conflicts);
+}
+
+/// The kinds of the conflicting accesses and loans, for each
+/// invalidation due to `invalidates_by_access`.
+pub(super) fn conflict_kinds<Region, Loan, Point, Variable, Path>(
+    all_facts: &AllFacts<Region, Loan, Point, Variable, Path>,
+) -> FxHashMap<(Point, Loan), BTreeSet<(AccessKind, LoanKind)>>
+where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    Variable: Atom,
+    Path: Atom,
+{
+    let mut kinds: FxHashMap<_, BTreeSet<_>> = FxHashMap::default();
+    for &(p, b, access, loan) in &all_facts.invalidates_by_access {
+        if access.conflicts_with(loan) {
+            kinds
+                .entry((p, b))
+                .or_default()
+                .insert((access, loan));
+        }
+    }
+    kinds
+}
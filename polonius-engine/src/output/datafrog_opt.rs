@@ -11,15 +11,26 @@
 use std::collections::{BTreeMap, BTreeSet};
 use std::time::Instant;
 
-use crate::output::Output;
+use output::profile::Profiler;
+use output::two_phase;
+use output::Output;
 
 use datafrog::{Iteration, Relation};
-use polonius_engine::{AllFacts, Atom};
+use facts::{AllFacts, Atom};
 
-pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
+macro_rules! watch_variables {
+    ($profiler:ident, $($variable:ident,)*) => {
+        $(
+            $profiler.watch(stringify!($variable), &$variable);
+        )*
+    }
+}
+
+pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom, Variable: Atom, Path: Atom>(
     dump_enabled: bool,
-    mut all_facts: AllFacts<Region, Loan, Point>,
-) -> Output<Region, Loan, Point> {
+    profile_enabled: bool,
+    mut all_facts: AllFacts<Region, Loan, Point, Variable, Path>,
+) -> Output<Region, Loan, Point, Path> {
     // Declare that each universal region is live at every point.
     let all_points: BTreeSet<Point> = all_facts
         .cfg_edge
@@ -34,11 +45,14 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
         }
     }
 
+    two_phase::remove_reads_of_reserved_loans(&mut all_facts);
+
     let timer = Instant::now();
 
     let mut result = Output::new(dump_enabled);
+    let mut profiler = Profiler::new(profile_enabled);
 
-    let errors = {
+    let (borrow_live_at, errors) = {
         // Create a new iteration context, ...
         let mut iteration = Iteration::new();
 
@@ -107,21 +121,73 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
         subset.insert(all_facts.outlives.into());
         requires.insert(all_facts.borrow_region.into());
 
+        // the rules are run through the profiler, which only records
+        // anything when `profile_enabled` is set.
+        watch_variables!(
+            profiler,
+            subset,
+            subset_1,
+            subset_2,
+            subset_r1p,
+            subset_p,
+            requires,
+            requires_1,
+            requires_2,
+            requires_bp,
+            requires_rp,
+            borrow_live_at,
+            live_to_dead_regions,
+            live_to_dead_regions_1,
+            live_to_dead_regions_2,
+            live_to_dead_regions_r2pq,
+            dead_region_requires,
+            dead_region_requires_1,
+            dead_region_requires_2,
+            dead_can_reach_origins,
+            dead_can_reach,
+            dead_can_reach_1,
+            dead_can_reach_r2q,
+            dead_can_reach_live,
+            dead_can_reach_live_r1pq,
+            errors,
+        );
+
         // .. and then start iterating rules!
         while iteration.changed() {
+            profiler.round();
+
             // remap fields to re-index by the different keys
-            subset_r1p.from_map(&subset, |&(r1, r2, p)| ((r1, p), r2));
-            subset_p.from_map(&subset, |&(r1, r2, p)| (p, (r1, r2)));
+            profiler.map("subset_r1p", &subset_r1p, &subset, |&(r1, r2, p)| {
+                ((r1, p), r2)
+            });
+            profiler.map("subset_p", &subset_p, &subset, |&(r1, r2, p)| (p, (r1, r2)));
 
-            requires_bp.from_map(&requires, |&(r, b, p)| ((b, p), r));
-            requires_rp.from_map(&requires, |&(r, b, p)| ((r, p), b));
+            profiler.map("requires_bp", &requires_bp, &requires, |&(r, b, p)| {
+                ((b, p), r)
+            });
+            profiler.map("requires_rp", &requires_rp, &requires, |&(r, b, p)| {
+                ((r, p), b)
+            });
 
-            live_to_dead_regions_r2pq
-                .from_map(&live_to_dead_regions, |&(r1, r2, p, q)| ((r2, p, q), r1));
+            profiler.map(
+                "live_to_dead_regions_r2pq",
+                &live_to_dead_regions_r2pq,
+                &live_to_dead_regions,
+                |&(r1, r2, p, q)| ((r2, p, q), r1),
+            );
 
-            dead_can_reach_r2q.from_map(&dead_can_reach, |&(r1, r2, p, q)| ((r2, q), (r1, p)));
-            dead_can_reach_live_r1pq
-                .from_map(&dead_can_reach_live, |&((r1, p, q), r2)| ((r1, p, q), r2));
+            profiler.map(
+                "dead_can_reach_r2q",
+                &dead_can_reach_r2q,
+                &dead_can_reach,
+                |&(r1, r2, p, q)| ((r2, q), (r1, p)),
+            );
+            profiler.map(
+                "dead_can_reach_live_r1pq",
+                &dead_can_reach_live_r1pq,
+                &dead_can_reach_live,
+                |&((r1, p, q), r2)| ((r1, p, q), r2),
+            );
 
             // it's now time ... to datafrog:
 
@@ -154,14 +220,23 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
             //   cfg_edge(P, Q),
             //   region_live_at(R1, Q),
             //   !region_live_at(R2, Q).
-            live_to_dead_regions_1
-                .from_join(&subset_p, &cfg_edge, |&p, &(r1, r2), &q| ((r1, q), (r2, p)));
-            live_to_dead_regions_2.from_join(
+            profiler.join(
+                "live_to_dead_regions_1",
+                &live_to_dead_regions_1,
+                &subset_p,
+                &cfg_edge,
+                |&p, &(r1, r2), &q| ((r1, q), (r2, p)),
+            );
+            profiler.join(
+                "live_to_dead_regions_2",
+                &live_to_dead_regions_2,
                 &live_to_dead_regions_1,
                 &region_live_at_var,
                 |&(r1, q), &(r2, p), &()| ((r2, q), (r1, p)),
             );
-            live_to_dead_regions.from_antijoin(
+            profiler.antijoin(
+                "live_to_dead_regions",
+                &live_to_dead_regions,
                 &live_to_dead_regions_2,
                 &region_live_at_rel,
                 |&(r2, q), &(r1, p)| (r1, r2, p, q),
@@ -177,13 +252,23 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
             //   !killed(B, P),
             //   cfg_edge(P, Q),
             //   !region_live_at(R, Q).
-            dead_region_requires_1.from_antijoin(&requires_bp, &killed, |&(b, p), &r| (p, (b, r)));
-            dead_region_requires_2.from_join(
+            profiler.antijoin(
+                "dead_region_requires_1",
+                &dead_region_requires_1,
+                &requires_bp,
+                &killed,
+                |&(b, p), &r| (p, (b, r)),
+            );
+            profiler.join(
+                "dead_region_requires_2",
+                &dead_region_requires_2,
                 &dead_region_requires_1,
                 &cfg_edge,
                 |&p, &(b, r), &q| ((r, q), (b, p)),
             );
-            dead_region_requires.from_antijoin(
+            profiler.antijoin(
+                "dead_region_requires",
+                &dead_region_requires,
                 &dead_region_requires_2,
                 &region_live_at_rel,
                 |&(r, q), &(b, p)| ((r, p, q), b),
@@ -194,8 +279,18 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
             // Contains dead regions where we are interested
             // in computing the transitive closure of things they
             // can reach.
-            dead_can_reach_origins.from_map(&live_to_dead_regions, |&(_r1, r2, p, q)| ((r2, p), q));
-            dead_can_reach_origins.from_map(&dead_region_requires, |&((r, p, q), _b)| ((r, p), q));
+            profiler.map(
+                "dead_can_reach_origins (live_to_dead_regions)",
+                &dead_can_reach_origins,
+                &live_to_dead_regions,
+                |&(_r1, r2, p, q)| ((r2, p), q),
+            );
+            profiler.map(
+                "dead_can_reach_origins (dead_region_requires)",
+                &dead_can_reach_origins,
+                &dead_region_requires,
+                |&((r, p, q), _b)| ((r, p), q),
+            );
 
             // .decl dead_can_reach(R1, R2, P, Q)
             //
@@ -209,9 +304,13 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
             // dead_can_reach(R1, R2, P, Q) :-
             //   dead_can_reach_origins(R1, P, Q),
             //   subset(R1, R2, P).
-            dead_can_reach.from_join(&dead_can_reach_origins, &subset_r1p, |&(r1, p), &q, &r2| {
-                (r1, r2, p, q)
-            });
+            profiler.join(
+                "dead_can_reach (origins)",
+                &dead_can_reach,
+                &dead_can_reach_origins,
+                &subset_r1p,
+                |&(r1, p), &q, &r2| (r1, r2, p, q),
+            );
 
             // dead_can_reach(R1, R3, P, Q) :-
             //   dead_can_reach(R1, R2, P, Q),
@@ -221,12 +320,16 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
             // This is the "transitive closure" rule, but
             // note that we only apply it with the
             // "intermediate" region R2 is dead at Q.
-            dead_can_reach_1.from_antijoin(
+            profiler.antijoin(
+                "dead_can_reach_1",
+                &dead_can_reach_1,
                 &dead_can_reach_r2q,
                 &region_live_at_rel,
                 |&(r2, q), &(r1, p)| ((r2, p), (r1, q)),
             );
-            dead_can_reach.from_join(
+            profiler.join(
+                "dead_can_reach (transitive)",
+                &dead_can_reach,
                 &dead_can_reach_1,
                 &subset_r1p,
                 |&(_r2, p), &(r1, q), &r3| (r1, r3, p, q),
@@ -240,7 +343,9 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
             // subset of the full `dead_can_reach` relation
             // where we filter down to those cases where R2 is
             // live in Q.
-            dead_can_reach_live.from_join(
+            profiler.join(
+                "dead_can_reach_live",
+                &dead_can_reach_live,
                 &dead_can_reach_r2q,
                 &region_live_at_var,
                 |&(r2, q), &(r1, p), &()| ((r1, p, q), r2),
@@ -254,18 +359,34 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
             //
             // Carry `R1 <= R2` from P into Q if both `R1` and
             // `R2` are live in Q.
-            subset_1.from_join(&subset_p, &cfg_edge, |&_p, &(r1, r2), &q| ((r1, q), r2));
-            subset_2.from_join(&subset_1, &region_live_at_var, |&(r1, q), &r2, &()| {
-                ((r2, q), r1)
-            });
-            subset.from_join(&subset_2, &region_live_at_var, |&(r2, q), &r1, &()| {
-                (r1, r2, q)
-            });
+            profiler.join(
+                "subset_1",
+                &subset_1,
+                &subset_p,
+                &cfg_edge,
+                |&_p, &(r1, r2), &q| ((r1, q), r2),
+            );
+            profiler.join(
+                "subset_2",
+                &subset_2,
+                &subset_1,
+                &region_live_at_var,
+                |&(r1, q), &r2, &()| ((r2, q), r1),
+            );
+            profiler.join(
+                "subset (cfg_edge)",
+                &subset,
+                &subset_2,
+                &region_live_at_var,
+                |&(r2, q), &r1, &()| (r1, r2, q),
+            );
 
             // subset(R1, R3, Q) :-
             //   live_to_dead_regions(R1, R2, P, Q),
             //   dead_can_reach_live(R2, R3, P, Q).
-            subset.from_join(
+            profiler.join(
+                "subset (dead_can_reach_live)",
+                &subset,
                 &live_to_dead_regions_r2pq,
                 &dead_can_reach_live_r1pq,
                 |&(_r2, _p, q), &r1, &r3| (r1, r3, q),
@@ -280,7 +401,9 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
             // that case, for each region `R2` live in `Q`
             // where `R1 <= R2` in P, we add `R2 requires B`
             // to `Q`.
-            requires.from_join(
+            profiler.join(
+                "requires (dead_can_reach_live)",
+                &requires,
                 &dead_region_requires,
                 &dead_can_reach_live_r1pq,
                 |&(_r1, _p, q), &b, &r2| (r2, b, q),
@@ -291,22 +414,48 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
             //   !killed(B, P),
             //   cfg_edge(P, Q),
             //   region_live_at(R, Q).
-            requires_1.from_antijoin(&requires_bp, &killed, |&(b, p), &r| (p, (r, b)));
-            requires_2.from_join(&requires_1, &cfg_edge, |&_p, &(r, b), &q| ((r, q), b));
-            requires.from_join(&requires_2, &region_live_at_var, |&(r, q), &b, &()| {
-                (r, b, q)
-            });
+            profiler.antijoin(
+                "requires_1",
+                &requires_1,
+                &requires_bp,
+                &killed,
+                |&(b, p), &r| (p, (r, b)),
+            );
+            profiler.join(
+                "requires_2",
+                &requires_2,
+                &requires_1,
+                &cfg_edge,
+                |&_p, &(r, b), &q| ((r, q), b),
+            );
+            profiler.join(
+                "requires (cfg_edge)",
+                &requires,
+                &requires_2,
+                &region_live_at_var,
+                |&(r, q), &b, &()| (r, b, q),
+            );
 
             // .decl borrow_live_at(B, P) -- true if the restrictions of the borrow B
             // need to be enforced at the point P
             //
             // borrow_live_at(B, P) :- requires(R, B, P), region_live_at(R, P)
-            borrow_live_at.from_join(&requires_rp, &region_live_at_var, |&(_r, p), &b, &()| {
-                ((b, p), ())
-            });
+            profiler.join(
+                "borrow_live_at",
+                &borrow_live_at,
+                &requires_rp,
+                &region_live_at_var,
+                |&(_r, p), &b, &()| ((b, p), ()),
+            );
 
             // .decl errors(B, P) :- invalidates(B, P), borrow_live_at(B, P).
-            errors.from_join(&invalidates, &borrow_live_at, |&(b, p), &(), &()| (b, p));
+            profiler.join(
+                "errors",
+                &errors,
+                &invalidates,
+                &borrow_live_at,
+                |&(b, p), &(), &()| (b, p),
+            );
         }
 
         if dump_enabled {
@@ -339,28 +488,27 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
                     .or_insert(BTreeSet::new())
                     .insert(*borrow);
             }
-
-            let borrow_live_at = borrow_live_at.complete();
-            for ((borrow, location), ()) in &borrow_live_at.elements {
-                result
-                    .borrow_live_at
-                    .entry(*location)
-                    .or_insert(Vec::new())
-                    .push(*borrow);
-            }
         }
 
-        errors.complete()
+        (borrow_live_at.complete(), errors.complete())
     };
 
     if dump_enabled {
-        println!(
+        eprintln!(
             "errors is complete: {} tuples, {:?}",
             errors.len(),
             timer.elapsed()
         );
     }
 
+    for ((borrow, location), ()) in &borrow_live_at.elements {
+        result
+            .borrow_live_at
+            .entry(*location)
+            .or_insert(Vec::new())
+            .push(*borrow);
+    }
+
     for (borrow, location) in &errors.elements {
         result
             .potential_errors
@@ -369,5 +517,7 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
             .push(*borrow);
     }
 
+    result.profile = profiler.finish();
+
     result
 }
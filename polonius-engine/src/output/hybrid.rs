@@ -0,0 +1,68 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Runs the cheap location-insensitive analysis first, and only falls
+//! back to the location-sensitive one when it reports potential errors.
+//!
+//! The location-insensitive analysis is an over-approximation: if it
+//! finds no errors, neither can `DatafrogOpt`. And since loans are
+//! tracked independently of one another, the second pass can be
+//! restricted to the loans that were flagged in the first one.
+//! Consequently, `borrow_live_at` (and the debugging relations) only
+//! contain results for those loans, and are empty when there are none.
+
+use fxhash::FxHashSet;
+use std::time::Instant;
+
+use output::datafrog_opt;
+use output::location_insensitive;
+use output::Output;
+
+use facts::{AllFacts, Atom};
+
+pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom, Variable: Atom, Path: Atom>(
+    dump_enabled: bool,
+    mut all_facts: AllFacts<Region, Loan, Point, Variable, Path>,
+) -> Output<Region, Loan, Point, Path> {
+    let timer = Instant::now();
+
+    let insensitive = location_insensitive::compute(dump_enabled, all_facts.clone());
+    if insensitive.potential_errors.is_empty() {
+        // The location-insensitive relations are over-approximations,
+        // and only computed with `dump_enabled`: return none of them.
+        return Output::new(dump_enabled);
+    }
+
+    let flagged_loans: FxHashSet<Loan> = insensitive
+        .potential_errors
+        .values()
+        .flat_map(|loans| loans.iter().cloned())
+        .collect();
+
+    if dump_enabled {
+        eprintln!(
+            "location-insensitive pass flagged {} loans, {:?}",
+            flagged_loans.len(),
+            timer.elapsed()
+        );
+    }
+
+    all_facts
+        .borrow_region
+        .retain(|&(_r, b, _p)| flagged_loans.contains(&b));
+    all_facts
+        .killed
+        .retain(|&(b, _p)| flagged_loans.contains(&b));
+    all_facts
+        .invalidates
+        .retain(|&(_p, b)| flagged_loans.contains(&b));
+
+    datafrog_opt::compute(dump_enabled, false, all_facts)
+}
@@ -0,0 +1,206 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Computes the move paths that may be initialized, or moved out, at
+//! each point, as a forward dataflow analysis over `cfg_edge`, and the
+//! `move_errors` where a path is accessed while maybe moved out.
+//!
+//! Every path starts out uninitialized, as if moved out at the entry
+//! points of the CFG (those without predecessors), unless it is
+//! assigned there: the fact generator is expected to emit
+//! `path_assigned_at` facts at the entry for the function arguments.
+//! Accessing a path which is never assigned is thus an error too.
+//!
+//! Assigning, moving or accessing a path does the same to all of its
+//! descendants: moving `a` also moves `a.b`, and accessing `a` needs
+//! `a.b` to be initialized.
+
+use fxhash::FxHashSet;
+
+use output::Output;
+use facts::{AllFacts, Atom};
+
+use datafrog::{Iteration, Relation};
+
+pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom, Variable: Atom, Path: Atom>(
+    dump_enabled: bool,
+    all_facts: &AllFacts<Region, Loan, Point, Variable, Path>,
+) -> Output<Region, Loan, Point, Path> {
+    let mut result = Output::new(dump_enabled);
+    if all_facts.path_assigned_at.is_empty()
+        && all_facts.path_moved_at.is_empty()
+        && all_facts.path_accessed_at.is_empty()
+    {
+        return result;
+    }
+
+    // The facts about each path, extended to its descendants.
+    let (path_assigned_at, path_moved_at, path_accessed_at) = {
+        let mut iteration = Iteration::new();
+
+        let child = iteration.variable::<(Path, Path)>("child");
+        let descendant = iteration.variable::<(Path, Path)>("descendant");
+        let path_assigned_at = iteration.variable::<(Path, Point)>("path_assigned_at");
+        let path_moved_at = iteration.variable::<(Path, Point)>("path_moved_at");
+        let path_accessed_at = iteration.variable::<(Path, Point)>("path_accessed_at");
+
+        child.insert(all_facts.child.clone().into());
+        descendant.insert(Relation::from(
+            all_facts.child.iter().map(|&(child, parent)| (parent, child)),
+        ));
+        path_assigned_at.insert(all_facts.path_assigned_at.clone().into());
+        path_moved_at.insert(all_facts.path_moved_at.clone().into());
+        path_accessed_at.insert(all_facts.path_accessed_at.clone().into());
+
+        while iteration.changed() {
+            // descendant(M1, M3) :- child(M2, M1), descendant(M2, M3).
+            descendant.from_join(&child, &descendant, |&_m2, &m1, &m3| (m1, m3));
+
+            // path_assigned_at(M2, P) :- path_assigned_at(M1, P), descendant(M1, M2).
+            path_assigned_at.from_join(&path_assigned_at, &descendant, |&_m1, &p, &m2| (m2, p));
+
+            // path_moved_at(M2, P) :- path_moved_at(M1, P), descendant(M1, M2).
+            path_moved_at.from_join(&path_moved_at, &descendant, |&_m1, &p, &m2| (m2, p));
+
+            // path_accessed_at(M2, P) :- path_accessed_at(M1, P), descendant(M1, M2).
+            path_accessed_at.from_join(&path_accessed_at, &descendant, |&_m1, &p, &m2| (m2, p));
+        }
+
+        (
+            path_assigned_at.complete(),
+            path_moved_at.complete(),
+            path_accessed_at.complete(),
+        )
+    };
+
+    // The paths which start out uninitialized at each entry point, i.e.
+    // each point without predecessors, even if the CFG has no edges.
+    let uninitialized_at_entry: Vec<(Path, Point)> = {
+        let path_facts = path_assigned_at
+            .iter()
+            .chain(path_moved_at.iter())
+            .chain(path_accessed_at.iter());
+        let targets: FxHashSet<Point> = all_facts.cfg_edge.iter().map(|&(_, q)| q).collect();
+        let entries: FxHashSet<Point> = all_facts
+            .cfg_edge
+            .iter()
+            .map(|&(p, _)| p)
+            .chain(path_facts.clone().map(|&(_, p)| p))
+            .filter(|p| !targets.contains(p))
+            .collect();
+        let paths: FxHashSet<Path> = path_facts.map(|&(m, _)| m).collect();
+        let assigned: FxHashSet<(Path, Point)> = path_assigned_at.iter().cloned().collect();
+
+        let mut uninitialized = Vec::new();
+        for &m in &paths {
+            for &p in &entries {
+                if !assigned.contains(&(m, p)) {
+                    uninitialized.push((m, p));
+                }
+            }
+        }
+        uninitialized
+    };
+
+    let mut iteration = Iteration::new();
+
+    // static inputs
+    let cfg_edge = iteration.variable::<(Point, Point)>("cfg_edge");
+    let path_accessed_at_mp = iteration.variable::<((Path, Point), ())>("path_accessed_at_mp");
+    let uninitialized_at_entry_mp =
+        iteration.variable::<((Path, Point), ())>("uninitialized_at_entry_mp");
+
+    // variables, indices for the joins with `cfg_edge`, and temporaries
+    let maybe_initialized = iteration.variable::<(Path, Point)>("maybe_initialized");
+    let maybe_initialized_p = iteration.variable_indistinct("maybe_initialized_p");
+    let maybe_initialized_1 = iteration.variable_indistinct("maybe_initialized_1");
+    let maybe_moved = iteration.variable::<(Path, Point)>("maybe_moved");
+    let maybe_moved_p = iteration.variable_indistinct("maybe_moved_p");
+    let maybe_moved_1 = iteration.variable_indistinct("maybe_moved_1");
+
+    // output
+    let move_errors = iteration.variable::<(Point, Path)>("move_errors");
+
+    // load initial facts.
+    cfg_edge.insert(all_facts.cfg_edge.clone().into());
+    path_accessed_at_mp.insert(Relation::from(
+        path_accessed_at.iter().map(|&(m, p)| ((m, p), ())),
+    ));
+    maybe_initialized.insert(path_assigned_at.iter().cloned().into());
+    maybe_moved.insert(path_moved_at.iter().cloned().into());
+    uninitialized_at_entry_mp.insert(Relation::from(
+        uninitialized_at_entry.iter().map(|&(m, p)| ((m, p), ())),
+    ));
+    maybe_moved.insert(uninitialized_at_entry.into());
+
+    while iteration.changed() {
+        maybe_initialized_p.from_map(&maybe_initialized, |&(m, p)| (p, m));
+        maybe_moved_p.from_map(&maybe_moved, |&(m, p)| (p, m));
+
+        // maybe_initialized(M, Q) :-
+        //   maybe_initialized(M, P),
+        //   cfg_edge(P, Q),
+        //   !path_moved_at(M, Q).
+        maybe_initialized_1.from_join(&maybe_initialized_p, &cfg_edge, |&_p, &m, &q| ((m, q), ()));
+        maybe_initialized.from_antijoin(&maybe_initialized_1, &path_moved_at, |&(m, q), &()| {
+            (m, q)
+        });
+
+        // maybe_moved(M, Q) :-
+        //   maybe_moved(M, P),
+        //   cfg_edge(P, Q),
+        //   !path_assigned_at(M, Q).
+        maybe_moved_1.from_join(&maybe_moved_p, &cfg_edge, |&_p, &m, &q| ((m, q), ()));
+        maybe_moved.from_antijoin(&maybe_moved_1, &path_assigned_at, |&(m, q), &()| (m, q));
+
+        // move_errors(Q, M) :-
+        //   maybe_moved(M, P),
+        //   cfg_edge(P, Q),
+        //   path_accessed_at(M, Q).
+        move_errors.from_join(&maybe_moved_1, &path_accessed_at_mp, |&(m, q), &(), &()| (q, m));
+
+        // move_errors(P, M) :-
+        //   uninitialized_at_entry(M, P),
+        //   path_accessed_at(M, P).
+        move_errors.from_join(
+            &uninitialized_at_entry_mp,
+            &path_accessed_at_mp,
+            |&(m, p), &(), &()| (p, m),
+        );
+    }
+
+    if dump_enabled {
+        for &(path, location) in &maybe_initialized.complete().elements {
+            result
+                .maybe_initialized
+                .entry(location)
+                .or_insert(vec![])
+                .push(path);
+        }
+
+        for &(path, location) in &maybe_moved.complete().elements {
+            result
+                .maybe_moved
+                .entry(location)
+                .or_insert(vec![])
+                .push(path);
+        }
+    }
+
+    for &(location, path) in &move_errors.complete().elements {
+        result
+            .move_errors
+            .entry(location)
+            .or_insert(vec![])
+            .push(path);
+    }
+
+    result
+}
@@ -0,0 +1,58 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use facts::{AllFacts, Atom};
+
+use datafrog::{Iteration, Relation};
+
+/// Computes the `killed` facts from the move path facts, instead of
+/// taking the ones rustc emits: a loan is killed where some prefix of
+/// the path it borrows is assigned, using `loan_path`, `child` and
+/// `path_assigned_at`. Assigning `a` kills a loan of `a.b`, but
+/// assigning `a.b` doesn't kill a loan of `a` or of `a.c`.
+pub fn derive_killed<Region, Loan, Point, Variable, Path>(
+    all_facts: &AllFacts<Region, Loan, Point, Variable, Path>,
+) -> Vec<(Loan, Point)>
+where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    Variable: Atom,
+    Path: Atom,
+{
+    let mut iteration = Iteration::new();
+
+    // static inputs
+    let child = iteration.variable::<(Path, Path)>("child");
+    let path_assigned_at = iteration.variable::<(Path, Point)>("path_assigned_at");
+
+    // `loan_prefix(M, B)` when M is a prefix of the path borrowed by B
+    let loan_prefix = iteration.variable::<(Path, Loan)>("loan_prefix");
+
+    // output
+    let killed = iteration.variable::<(Loan, Point)>("killed");
+
+    // load initial facts.
+    child.insert(all_facts.child.clone().into());
+    path_assigned_at.insert(all_facts.path_assigned_at.clone().into());
+    loan_prefix.insert(Relation::from(
+        all_facts.loan_path.iter().map(|&(b, m)| (m, b)),
+    ));
+
+    while iteration.changed() {
+        // loan_prefix(M2, B) :- loan_prefix(M1, B), child(M1, M2).
+        loan_prefix.from_join(&loan_prefix, &child, |&_m1, &b, &m2| (m2, b));
+
+        // killed(B, P) :- loan_prefix(M, B), path_assigned_at(M, P).
+        killed.from_join(&loan_prefix, &path_assigned_at, |&_m, &b, &p| (b, p));
+    }
+
+    killed.complete().elements
+}
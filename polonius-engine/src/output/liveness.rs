@@ -0,0 +1,87 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Computes `region_live_at` from the variable facts, as a backward
+//! dataflow analysis over `cfg_edge`: a variable is live at a point if
+//! it may be used later without being overwritten in between, and the
+//! regions in its type are then live as well.
+
+use datafrog::{Iteration, Relation};
+use facts::{AllFacts, Atom};
+
+pub(super) fn compute_live_regions<Region, Loan, Point, Variable, Path>(
+    all_facts: &AllFacts<Region, Loan, Point, Variable, Path>,
+) -> Vec<(Region, Point)>
+where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    Variable: Atom,
+    Path: Atom,
+{
+    if all_facts.var_used.is_empty() && all_facts.var_drop_used.is_empty() {
+        return Vec::new();
+    }
+
+    let mut iteration = Iteration::new();
+
+    // static inputs
+    let cfg_edge_q = iteration.variable::<(Point, Point)>("cfg_edge_q");
+    let var_defined: Relation<(Variable, Point)> = all_facts.var_defined.clone().into();
+    let var_uses_region = iteration.variable::<(Variable, Region)>("var_uses_region");
+    let var_drops_region = iteration.variable::<(Variable, Region)>("var_drops_region");
+
+    // variables, with an index by point for the joins with `cfg_edge`
+    let var_live = iteration.variable::<(Variable, Point)>("var_live");
+    let var_live_q = iteration.variable_indistinct::<(Point, Variable)>("var_live_q");
+    let var_live_1 = iteration.variable_indistinct("var_live_1");
+    let var_drop_live = iteration.variable::<(Variable, Point)>("var_drop_live");
+    let var_drop_live_q = iteration.variable_indistinct::<(Point, Variable)>("var_drop_live_q");
+    let var_drop_live_1 = iteration.variable_indistinct("var_drop_live_1");
+
+    // output
+    let region_live_at = iteration.variable::<(Region, Point)>("region_live_at");
+
+    // load initial facts.
+    cfg_edge_q.insert(Relation::from(
+        all_facts.cfg_edge.iter().map(|&(p, q)| (q, p)),
+    ));
+    var_uses_region.insert(all_facts.var_uses_region.clone().into());
+    var_drops_region.insert(all_facts.var_drops_region.clone().into());
+    var_live.insert(all_facts.var_used.clone().into());
+    var_drop_live.insert(all_facts.var_drop_used.clone().into());
+
+    while iteration.changed() {
+        var_live_q.from_map(&var_live, |&(v, q)| (q, v));
+        var_drop_live_q.from_map(&var_drop_live, |&(v, q)| (q, v));
+
+        // var_live(V, P) :-
+        //   var_live(V, Q),
+        //   cfg_edge(P, Q),
+        //   !var_defined(V, P).
+        var_live_1.from_join(&var_live_q, &cfg_edge_q, |&_q, &v, &p| ((v, p), ()));
+        var_live.from_antijoin(&var_live_1, &var_defined, |&(v, p), &()| (v, p));
+
+        // var_drop_live(V, P) :-
+        //   var_drop_live(V, Q),
+        //   cfg_edge(P, Q),
+        //   !var_defined(V, P).
+        var_drop_live_1.from_join(&var_drop_live_q, &cfg_edge_q, |&_q, &v, &p| ((v, p), ()));
+        var_drop_live.from_antijoin(&var_drop_live_1, &var_defined, |&(v, p), &()| (v, p));
+
+        // region_live_at(R, P) :- var_live(V, P), var_uses_region(V, R).
+        region_live_at.from_join(&var_live, &var_uses_region, |&_v, &p, &r| (r, p));
+
+        // region_live_at(R, P) :- var_drop_live(V, P), var_drops_region(V, R).
+        region_live_at.from_join(&var_drop_live, &var_drops_region, |&_v, &p, &r| (r, p));
+    }
+
+    region_live_at.complete().elements
+}
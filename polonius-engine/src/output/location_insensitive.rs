@@ -11,15 +11,15 @@
 use std::collections::BTreeSet;
 use std::time::Instant;
 
-use crate::output::Output;
+use output::Output;
 
 use datafrog::{Iteration, Relation};
-use polonius_engine::{AllFacts, Atom};
+use facts::{AllFacts, Atom};
 
-pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
+pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom, Variable: Atom, Path: Atom>(
     dump_enabled: bool,
-    mut all_facts: AllFacts<Region, Loan, Point>,
-) -> Output<Region, Loan, Point> {
+    mut all_facts: AllFacts<Region, Loan, Point, Variable, Path>,
+) -> Output<Region, Loan, Point, Path> {
     let all_points: BTreeSet<Point> = all_facts
         .cfg_edge
         .iter()
@@ -136,7 +136,7 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
     };
 
     if dump_enabled {
-        println!(
+        eprintln!(
             "potential_errors is complete: {} tuples, {:?}",
             potential_errors.len(),
             potential_errors_start.elapsed()
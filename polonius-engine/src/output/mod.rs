@@ -0,0 +1,249 @@
+// Copyright 2017 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use fxhash::FxHashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+
+use facts::{AccessKind, AllFacts, Atom, LoanKind};
+
+mod access;
+mod datafrog_opt;
+mod hybrid;
+mod initialization;
+mod killed;
+mod liveness;
+mod location_insensitive;
+mod naive;
+mod profile;
+mod two_phase;
+
+pub use self::killed::derive_killed;
+pub use self::profile::{Profile, RuleProfile, VariableProfile};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Naive,
+    DatafrogOpt,
+    LocationInsensitive,
+
+    /// Only `potential_errors` (and `move_errors`) are the same as with
+    /// `DatafrogOpt`: `borrow_live_at` and the debugging relations only
+    /// cover the loans with potential errors.
+    Hybrid,
+}
+
+impl Algorithm {
+    pub fn variants() -> [&'static str; 4] {
+        ["Naive", "DatafrogOpt", "LocationInsensitive", "Hybrid"]
+    }
+}
+
+impl ::std::str::FromStr for Algorithm {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_ref() {
+            "naive" => Ok(Algorithm::Naive),
+            "datafrogopt" => Ok(Algorithm::DatafrogOpt),
+            "locationinsensitive" => Ok(Algorithm::LocationInsensitive),
+            "hybrid" => Ok(Algorithm::Hybrid),
+            _ => Err(String::from(
+                "valid values: Naive, DatafrogOpt, LocationInsensitive, Hybrid",
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Output<Region: Atom, Loan: Atom, Point: Atom, Path: Atom> {
+    pub borrow_live_at: FxHashMap<Point, Vec<Loan>>,
+
+    pub dump_enabled: bool,
+
+    // these are just for debugging
+    pub restricts: FxHashMap<Point, BTreeMap<Region, BTreeSet<Loan>>>,
+    pub restricts_anywhere: FxHashMap<Region, BTreeSet<Loan>>,
+    pub region_live_at: FxHashMap<Point, Vec<Region>>,
+    pub invalidates: FxHashMap<Point, Vec<Loan>>,
+    pub potential_errors: FxHashMap<Point, Vec<Loan>>,
+    pub subset: FxHashMap<Point, BTreeMap<Region, BTreeSet<Region>>>,
+    pub subset_anywhere: FxHashMap<Region, BTreeSet<Region>>,
+
+    /// For the `potential_errors` due to `invalidates_by_access` facts,
+    /// the kinds of the conflicting accesses and loans. Its keys are the
+    /// same points and loans as in `potential_errors`, which has an
+    /// entry for each of them: look an error `(point, loan)` up with
+    /// `error_kinds_at(point, loan)`. The errors only due to plain
+    /// `invalidates` facts have no kinds.
+    pub potential_error_kinds: FxHashMap<Point, BTreeMap<Loan, BTreeSet<(AccessKind, LoanKind)>>>,
+
+    /// The move paths accessed at each point while maybe uninitialized.
+    pub move_errors: FxHashMap<Point, Vec<Path>>,
+
+    /// The move paths maybe initialized, resp. maybe moved out or never
+    /// initialized, on exit from each point; only kept with `dump_enabled`.
+    pub maybe_initialized: FxHashMap<Point, Vec<Path>>,
+    pub maybe_moved: FxHashMap<Point, Vec<Path>>,
+
+    /// Per-rule and per-variable statistics, see `compute_profiled`.
+    pub profile: Option<Profile>,
+}
+
+impl<Region, Loan, Point, Path> Output<Region, Loan, Point, Path>
+where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    Path: Atom,
+{
+    /// Runs `algorithm` on `all_facts`. The regions live at each point
+    /// are the ones given by `region_live_at`, plus the ones computed
+    /// from the variable facts (`var_used` and so on), if any: either
+    /// can be used. The `move_errors` don't depend on the algorithm.
+    pub fn compute<Variable: Atom>(
+        all_facts: &AllFacts<Region, Loan, Point, Variable, Path>,
+        algorithm: Algorithm,
+        dump_enabled: bool,
+    ) -> Self {
+        let all_facts = with_derived_facts(all_facts);
+        let moves = initialization::compute(dump_enabled, &all_facts);
+        let conflict_kinds = access::conflict_kinds(&all_facts);
+        let result = match algorithm {
+            Algorithm::Naive => naive::compute(dump_enabled, all_facts),
+            Algorithm::DatafrogOpt => datafrog_opt::compute(dump_enabled, false, all_facts),
+            Algorithm::LocationInsensitive => {
+                location_insensitive::compute(dump_enabled, all_facts)
+            }
+            Algorithm::Hybrid => hybrid::compute(dump_enabled, all_facts),
+        };
+        result.with_moves(moves).with_error_kinds(&conflict_kinds)
+    }
+
+    /// Runs the `DatafrogOpt` analysis with its rules instrumented:
+    /// the returned output has a `profile` recording, for each rule,
+    /// the tuples it produced and the time spent in it, and for each
+    /// variable, how many new tuples it got and until which round.
+    pub fn compute_profiled<Variable: Atom>(
+        all_facts: &AllFacts<Region, Loan, Point, Variable, Path>,
+        dump_enabled: bool,
+    ) -> Self {
+        let all_facts = with_derived_facts(all_facts);
+        let moves = initialization::compute(dump_enabled, &all_facts);
+        let conflict_kinds = access::conflict_kinds(&all_facts);
+        datafrog_opt::compute(dump_enabled, true, all_facts)
+            .with_moves(moves)
+            .with_error_kinds(&conflict_kinds)
+    }
+
+    fn new(dump_enabled: bool) -> Self {
+        Output {
+            borrow_live_at: FxHashMap::default(),
+            restricts: FxHashMap::default(),
+            restricts_anywhere: FxHashMap::default(),
+            region_live_at: FxHashMap::default(),
+            invalidates: FxHashMap::default(),
+            potential_errors: FxHashMap::default(),
+            subset: FxHashMap::default(),
+            subset_anywhere: FxHashMap::default(),
+            potential_error_kinds: FxHashMap::default(),
+            move_errors: FxHashMap::default(),
+            maybe_initialized: FxHashMap::default(),
+            maybe_moved: FxHashMap::default(),
+            profile: None,
+            dump_enabled,
+        }
+    }
+
+    /// Takes the results of the initialization analysis from `moves`.
+    fn with_moves(mut self, moves: Self) -> Self {
+        self.move_errors = moves.move_errors;
+        self.maybe_initialized = moves.maybe_initialized;
+        self.maybe_moved = moves.maybe_moved;
+        self
+    }
+
+    /// Fills `potential_error_kinds` from the kinds of the conflicts.
+    fn with_error_kinds(
+        mut self,
+        conflict_kinds: &FxHashMap<(Point, Loan), BTreeSet<(AccessKind, LoanKind)>>,
+    ) -> Self {
+        for (&point, loans) in &self.potential_errors {
+            for &loan in loans {
+                if let Some(kinds) = conflict_kinds.get(&(point, loan)) {
+                    self.potential_error_kinds
+                        .entry(point)
+                        .or_default()
+                        .insert(loan, kinds.clone());
+                }
+            }
+        }
+        self
+    }
+
+    pub fn borrows_in_scope_at(&self, location: Point) -> &[Loan] {
+        match self.borrow_live_at.get(&location) {
+            Some(p) => p,
+            None => &[],
+        }
+    }
+
+    /// The kinds of the accesses and loans conflicting in the potential
+    /// error of `loan` at `location`, empty if it has none.
+    pub fn error_kinds_at(&self, location: Point, loan: Loan) -> Vec<(AccessKind, LoanKind)> {
+        self.potential_error_kinds
+            .get(&location)
+            .and_then(|loans| loans.get(&loan))
+            .map_or(vec![], |kinds| kinds.iter().cloned().collect())
+    }
+
+    pub fn move_errors_at(&self, location: Point) -> &[Path] {
+        match self.move_errors.get(&location) {
+            Some(v) => v,
+            None => &[],
+        }
+    }
+
+    pub fn restricts_at(&self, location: Point) -> Cow<'_, BTreeMap<Region, BTreeSet<Loan>>> {
+        assert!(self.dump_enabled);
+        match self.restricts.get(&location) {
+            Some(map) => Cow::Borrowed(map),
+            None => Cow::Owned(BTreeMap::default()),
+        }
+    }
+
+    pub fn regions_live_at(&self, location: Point) -> &[Region] {
+        assert!(self.dump_enabled);
+        match self.region_live_at.get(&location) {
+            Some(v) => v,
+            None => &[],
+        }
+    }
+
+    pub fn subsets_at(&self, location: Point) -> Cow<'_, BTreeMap<Region, BTreeSet<Region>>> {
+        assert!(self.dump_enabled);
+        match self.subset.get(&location) {
+            Some(v) => Cow::Borrowed(v),
+            None => Cow::Owned(BTreeMap::default()),
+        }
+    }
+}
+
+/// A copy of `all_facts` whose `region_live_at` also contains the
+/// regions made live by the variable facts, and whose `invalidates`
+/// also contains the conflicting `invalidates_by_access` facts.
+fn with_derived_facts<Region: Atom, Loan: Atom, Point: Atom, Variable: Atom, Path: Atom>(
+    all_facts: &AllFacts<Region, Loan, Point, Variable, Path>,
+) -> AllFacts<Region, Loan, Point, Variable, Path> {
+    let mut all_facts = all_facts.clone();
+    let live_regions = liveness::compute_live_regions(&all_facts);
+    all_facts.region_live_at.extend(live_regions);
+    access::add_conflicting_accesses(&mut all_facts);
+    all_facts
+}
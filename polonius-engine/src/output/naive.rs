@@ -13,15 +13,16 @@
 use std::collections::{BTreeMap, BTreeSet};
 use std::time::Instant;
 
-use crate::output::Output;
-use polonius_engine::{AllFacts, Atom};
+use output::two_phase;
+use output::Output;
+use facts::{AllFacts, Atom};
 
 use datafrog::{Iteration, Relation};
 
-pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
+pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom, Variable: Atom, Path: Atom>(
     dump_enabled: bool,
-    mut all_facts: AllFacts<Region, Loan, Point>,
-) -> Output<Region, Loan, Point> {
+    mut all_facts: AllFacts<Region, Loan, Point, Variable, Path>,
+) -> Output<Region, Loan, Point, Path> {
     let all_points: BTreeSet<Point> = all_facts
         .cfg_edge
         .iter()
@@ -35,18 +36,20 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
         }
     }
 
+    two_phase::remove_reads_of_reserved_loans(&mut all_facts);
+
     let mut result = Output::new(dump_enabled);
 
     let borrow_live_at_start = Instant::now();
 
-    let borrow_live_at = {
+    let (borrow_live_at, errors) = {
         // Create a new iteration context, ...
         let mut iteration = Iteration::new();
 
         // .. some variables, ..
         let subset = iteration.variable::<(Region, Region, Point)>("subset");
         let requires = iteration.variable::<(Region, Loan, Point)>("requires");
-        let borrow_live_at = iteration.variable::<(Loan, Point)>("borrow_live_at");
+        let borrow_live_at = iteration.variable::<((Loan, Point), ())>("borrow_live_at");
 
         // different indices for `subset`.
         let subset_r1p = iteration.variable_indistinct("subset_r1p");
@@ -67,6 +70,12 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
         let region_live_at = iteration.variable::<((Region, Point), ())>("region_live_at");
         let cfg_edge_p = iteration.variable::<(Point, Point)>("cfg_edge_p");
 
+        // `invalidates` facts, stored ready for joins
+        let invalidates = iteration.variable::<((Loan, Point), ())>("invalidates");
+
+        // output
+        let errors = iteration.variable("errors");
+
         // load initial facts.
         subset.insert(all_facts.outlives.into());
         requires.insert(all_facts.borrow_region.into());
@@ -74,6 +83,9 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
             all_facts.region_live_at.iter().map(|&(r, p)| ((r, p), ())),
         ));
         cfg_edge_p.insert(all_facts.cfg_edge.clone().into());
+        invalidates.insert(Relation::from(
+            all_facts.invalidates.iter().map(|&(p, b)| ((b, p), ())),
+        ));
 
         // .. and then start iterating rules!
         while iteration.changed() {
@@ -120,7 +132,12 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
             requires.from_join(&requires_2, &region_live_at, |&(r, q), &b, &()| (r, b, q));
 
             // borrow_live_at(B, P) :- requires(R, B, P), region_live_at(R, P)
-            borrow_live_at.from_join(&requires_rp, &region_live_at, |&(_r, p), &b, &()| (b, p));
+            borrow_live_at.from_join(&requires_rp, &region_live_at, |&(_r, p), &b, &()| {
+                ((b, p), ())
+            });
+
+            // errors(B, P) :- invalidates(B, P), borrow_live_at(B, P).
+            errors.from_join(&invalidates, &borrow_live_at, |&(b, p), &(), &()| (b, p));
         }
 
         if dump_enabled {
@@ -156,18 +173,18 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
             }
         }
 
-        borrow_live_at.complete()
+        (borrow_live_at.complete(), errors.complete())
     };
 
     if dump_enabled {
-        println!(
+        eprintln!(
             "borrow_live_at is complete: {} tuples, {:?}",
             borrow_live_at.len(),
             borrow_live_at_start.elapsed()
         );
     }
 
-    for (borrow, location) in &borrow_live_at.elements {
+    for ((borrow, location), ()) in &borrow_live_at.elements {
         result
             .borrow_live_at
             .entry(*location)
@@ -175,5 +192,13 @@ pub(super) fn compute<Region: Atom, Loan: Atom, Point: Atom>(
             .push(*borrow);
     }
 
+    for (borrow, location) in &errors.elements {
+        result
+            .potential_errors
+            .entry(*location)
+            .or_insert(Vec::new())
+            .push(*borrow);
+    }
+
     result
 }
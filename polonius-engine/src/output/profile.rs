@@ -0,0 +1,233 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Opt-in instrumentation of the datafrog rules: the rules are run
+//! through a `Profiler`, which records how many tuples each one
+//! produces and how long it takes, and how many new tuples each
+//! variable gets in each round.
+
+use datafrog::{Relation, Variable};
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::fmt;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// What a rule did over a whole computation.
+#[derive(Clone, Debug)]
+pub struct RuleProfile {
+    pub name: &'static str,
+    /// Number of times the rule was applied (i.e., rounds).
+    pub applications: usize,
+    /// Number of tuples produced, before de-duplication.
+    pub tuples: usize,
+    pub time: Duration,
+}
+
+/// What a variable went through over a whole computation.
+#[derive(Clone, Debug)]
+pub struct VariableProfile {
+    pub name: &'static str,
+    /// Number of new tuples added to the variable.
+    pub tuples: usize,
+    /// The last round in which the variable got new tuples.
+    pub last_changed: usize,
+}
+
+/// The instrumentation results of a computation.
+#[derive(Clone, Debug, Default)]
+pub struct Profile {
+    pub rounds: usize,
+    pub rules: Vec<RuleProfile>,
+    pub variables: Vec<VariableProfile>,
+}
+
+pub(super) struct Profiler {
+    enabled: bool,
+    profile: Profile,
+    watched: Vec<Box<dyn Fn() -> usize>>,
+}
+
+impl Profiler {
+    pub(super) fn new(enabled: bool) -> Self {
+        Profiler {
+            enabled,
+            profile: Profile::default(),
+            watched: vec![],
+        }
+    }
+
+    /// Records the new tuples of `variable` at each round.
+    pub(super) fn watch<Tuple: Ord + 'static>(
+        &mut self,
+        name: &'static str,
+        variable: &Variable<Tuple>,
+    ) {
+        if !self.enabled {
+            return;
+        }
+
+        let recent: Rc<RefCell<Relation<Tuple>>> = variable.recent.clone();
+        self.watched.push(Box::new(move || recent.borrow().len()));
+        self.profile.variables.push(VariableProfile {
+            name,
+            tuples: 0,
+            last_changed: 0,
+        });
+    }
+
+    /// To be called at the start of each round, once the variables
+    /// have been updated.
+    pub(super) fn round(&mut self) {
+        if !self.enabled {
+            return;
+        }
+
+        self.profile.rounds += 1;
+        for (recent_len, variable) in self.watched.iter().zip(&mut self.profile.variables) {
+            let new_tuples = recent_len();
+            if new_tuples > 0 {
+                variable.tuples += new_tuples;
+                variable.last_changed = self.profile.rounds;
+            }
+        }
+    }
+
+    pub(super) fn join<K: Ord, V1: Ord, V2: Ord, Tuple: Ord>(
+        &mut self,
+        name: &'static str,
+        output: &Variable<Tuple>,
+        input1: &Variable<(K, V1)>,
+        input2: &Variable<(K, V2)>,
+        mut logic: impl FnMut(&K, &V1, &V2) -> Tuple,
+    ) {
+        if !self.enabled {
+            return output.from_join(input1, input2, logic);
+        }
+
+        let start = Instant::now();
+        let mut tuples = 0;
+        output.from_join(input1, input2, |k, v1, v2| {
+            tuples += 1;
+            logic(k, v1, v2)
+        });
+        self.record(name, tuples, start.elapsed());
+    }
+
+    pub(super) fn antijoin<K: Ord, V: Ord, Tuple: Ord>(
+        &mut self,
+        name: &'static str,
+        output: &Variable<Tuple>,
+        input1: &Variable<(K, V)>,
+        input2: &Relation<K>,
+        mut logic: impl FnMut(&K, &V) -> Tuple,
+    ) {
+        if !self.enabled {
+            return output.from_antijoin(input1, input2, logic);
+        }
+
+        let start = Instant::now();
+        let mut tuples = 0;
+        output.from_antijoin(input1, input2, |k, v| {
+            tuples += 1;
+            logic(k, v)
+        });
+        self.record(name, tuples, start.elapsed());
+    }
+
+    pub(super) fn map<T2: Ord, Tuple: Ord>(
+        &mut self,
+        name: &'static str,
+        output: &Variable<Tuple>,
+        input: &Variable<T2>,
+        mut logic: impl FnMut(&T2) -> Tuple,
+    ) {
+        if !self.enabled {
+            return output.from_map(input, logic);
+        }
+
+        let start = Instant::now();
+        let mut tuples = 0;
+        output.from_map(input, |t| {
+            tuples += 1;
+            logic(t)
+        });
+        self.record(name, tuples, start.elapsed());
+    }
+
+    fn record(&mut self, name: &'static str, tuples: usize, time: Duration) {
+        let index = match self.profile.rules.iter().position(|rule| rule.name == name) {
+            Some(index) => index,
+            None => {
+                self.profile.rules.push(RuleProfile {
+                    name,
+                    applications: 0,
+                    tuples: 0,
+                    time: Duration::from_secs(0),
+                });
+                self.profile.rules.len() - 1
+            }
+        };
+
+        let rule = &mut self.profile.rules[index];
+        rule.applications += 1;
+        rule.tuples += tuples;
+        rule.time += time;
+    }
+
+    pub(super) fn finish(self) -> Option<Profile> {
+        if self.enabled {
+            Some(self.profile)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Profile {
+    /// Prints the rules, slowest first, then the variables.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut rules: Vec<_> = self.rules.iter().collect();
+        rules.sort_by_key(|rule| Reverse(rule.time));
+
+        writeln!(f, "{} rounds until fixpoint", self.rounds)?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:<60} {:>8} {:>12} {:>12}",
+            "rule", "rounds", "tuples", "time (ms)"
+        )?;
+        for rule in rules {
+            let millis = rule.time.as_secs() as f64 * 1000.0
+                + f64::from(rule.time.subsec_nanos()) * 0.000_001;
+            writeln!(
+                f,
+                "{:<60} {:>8} {:>12} {:>12.3}",
+                rule.name, rule.applications, rule.tuples, millis
+            )?;
+        }
+
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:<60} {:>8} {:>12}",
+            "variable", "last new", "tuples"
+        )?;
+        for variable in &self.variables {
+            writeln!(
+                f,
+                "{:<60} {:>8} {:>12}",
+                variable.name, variable.last_changed, variable.tuples
+            )?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+// Copyright 2018 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Two-phase borrows, like the `&mut v` in `v.push(v.len())`, are
+//! reserved first and only activated later: until then, they only
+//! conflict with writes, and reading the borrowed path is fine.
+//!
+//! The reads are the `invalidates_by_read` facts, and the
+//! `invalidates_by_access` facts of kind `Read` at points without
+//! other accesses to the loan.
+
+use fxhash::FxHashSet;
+
+use facts::{AccessKind, AllFacts, Atom};
+
+use datafrog::{Iteration, Relation};
+
+/// Removes from `invalidates` the reads of two-phase loans at the
+/// points where they can't have been activated yet.
+pub(super) fn remove_reads_of_reserved_loans<Region, Loan, Point, Variable, Path>(
+    all_facts: &mut AllFacts<Region, Loan, Point, Variable, Path>,
+) where
+    Region: Atom,
+    Loan: Atom,
+    Point: Atom,
+    Variable: Atom,
+    Path: Atom,
+{
+    if all_facts.loan_reserved_at.is_empty() {
+        return;
+    }
+
+    let mut iteration = Iteration::new();
+
+    // static inputs
+    let cfg_edge = iteration.variable::<(Point, Point)>("cfg_edge");
+    let loan_reserved_at: Relation<(Loan, Point)> = all_facts.loan_reserved_at.clone().into();
+
+    // `maybe_activated(B, P)` when the loan B may have been activated at
+    // point P, since it was last reserved
+    let maybe_activated = iteration.variable::<(Loan, Point)>("maybe_activated");
+    let maybe_activated_p = iteration.variable_indistinct("maybe_activated_p");
+    let maybe_activated_1 = iteration.variable_indistinct("maybe_activated_1");
+
+    cfg_edge.insert(all_facts.cfg_edge.clone().into());
+    maybe_activated.insert(all_facts.loan_activated_at.clone().into());
+
+    while iteration.changed() {
+        maybe_activated_p.from_map(&maybe_activated, |&(b, p)| (p, b));
+
+        // maybe_activated(B, Q) :-
+        //   maybe_activated(B, P),
+        //   cfg_edge(P, Q),
+        //   !loan_reserved_at(B, Q).
+        maybe_activated_1.from_join(&maybe_activated_p, &cfg_edge, |&_p, &b, &q| ((b, q), ()));
+        maybe_activated.from_antijoin(&maybe_activated_1, &loan_reserved_at, |&(b, q), &()| {
+            (b, q)
+        });
+    }
+
+    let maybe_activated: FxHashSet<(Loan, Point)> =
+        maybe_activated.complete().elements.into_iter().collect();
+    let two_phase_loans: FxHashSet<Loan> =
+        all_facts.loan_reserved_at.iter().map(|&(b, _)| b).collect();
+    let writes: FxHashSet<(Point, Loan)> = all_facts
+        .invalidates_by_access
+        .iter()
+        .filter(|&&(_, _, access, _)| access != AccessKind::Read)
+        .map(|&(p, b, _, _)| (p, b))
+        .collect();
+    let access_reads = all_facts
+        .invalidates_by_access
+        .iter()
+        .filter(|&&(p, b, access, _)| access == AccessKind::Read && !writes.contains(&(p, b)))
+        .map(|&(p, b, _, _)| (p, b));
+    let reserved_reads: FxHashSet<(Point, Loan)> = all_facts
+        .invalidates_by_read
+        .iter()
+        .cloned()
+        .chain(access_reads)
+        .filter(|&(p, b)| two_phase_loans.contains(&b) && !maybe_activated.contains(&(b, p)))
+        .collect();
+
+    all_facts
+        .invalidates
+        .retain(|invalidation| !reserved_reads.contains(invalidation));
+}
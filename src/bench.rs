@@ -0,0 +1,190 @@
+//! Runs the algorithms repeatedly on each fact directory and reports
+//! statistics on the timings, optionally checking them against a
+//! baseline saved by an earlier run.
+
+use crate::cli::{load_facts, timed};
+use crate::intern::InternerTables;
+use failure::{self, Error};
+use polonius_engine::{Algorithm, Output};
+use serde_json::{self, Value};
+use std::fs::File;
+use std::path::Path;
+use std::time::Duration;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+crate struct BenchOpt {
+    #[structopt(
+        short = "a",
+        default_value = "naive",
+        raw(
+            use_delimiter = "true",
+            possible_values = "&Algorithm::variants()",
+            case_insensitive = "true"
+        )
+    )]
+    algorithms: Vec<Algorithm>,
+    /// Number of measured runs per algorithm and directory
+    #[structopt(long = "runs", default_value = "10")]
+    runs: usize,
+    /// Number of unmeasured runs before the measured ones
+    #[structopt(long = "warmup", default_value = "1")]
+    warmup: usize,
+    /// Compare the median timings against this baseline file
+    #[structopt(long = "baseline")]
+    baseline: Option<String>,
+    /// Save the timings to this baseline file
+    #[structopt(long = "save-baseline")]
+    save_baseline: Option<String>,
+    /// How much slower than the baseline (in percent) counts as a regression
+    #[structopt(long = "threshold", default_value = "10")]
+    threshold: f64,
+    #[structopt(raw(required = "true"))]
+    fact_dirs: Vec<String>,
+}
+
+/// Summary statistics of a set of timings, in seconds.
+#[derive(Copy, Clone, Debug)]
+crate struct Stats {
+    crate min: f64,
+    crate median: f64,
+    crate mean: f64,
+    crate stddev: f64,
+}
+
+impl Stats {
+    crate fn from_samples(samples: &[f64]) -> Stats {
+        assert!(!samples.is_empty());
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let len = sorted.len();
+        let median = if len % 2 == 0 {
+            (sorted[len / 2 - 1] + sorted[len / 2]) / 2.0
+        } else {
+            sorted[len / 2]
+        };
+        let mean = sorted.iter().sum::<f64>() / len as f64;
+        let variance = sorted.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / len as f64;
+
+        Stats {
+            min: sorted[0],
+            median,
+            mean,
+            stddev: variance.sqrt(),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "min": self.min,
+            "median": self.median,
+            "mean": self.mean,
+            "stddev": self.stddev,
+        })
+    }
+}
+
+crate fn bench(opt: &BenchOpt) -> Result<(), Error> {
+    if opt.runs == 0 {
+        Err(failure::err_msg("`--runs` must be at least 1"))?;
+    }
+
+    let baseline: Option<Value> = match opt.baseline {
+        Some(ref path) => Some(serde_json::from_reader(File::open(path)?)?),
+        None => None,
+    };
+
+    let mut results = json!({});
+    let mut regressions = 0;
+
+    for facts_dir in &opt.fact_dirs {
+        println!("--------------------------------------------------");
+        println!("Directory: {}", facts_dir);
+
+        let mut load_times = vec![];
+        let mut compute_times = vec![vec![]; opt.algorithms.len()];
+        for run in 0..opt.warmup + opt.runs {
+            let tables = &mut InternerTables::new();
+            let (load_time, all_facts) = timed(|| load_facts(tables, Path::new(facts_dir), false));
+            let all_facts = all_facts?;
+
+            for (&algorithm, times) in opt.algorithms.iter().zip(&mut compute_times) {
+                let (compute_time, _) = timed(|| Output::compute(&all_facts, algorithm, false));
+                if run >= opt.warmup {
+                    times.push(seconds(compute_time));
+                }
+            }
+
+            if run >= opt.warmup {
+                load_times.push(seconds(load_time));
+            }
+        }
+
+        let load_stats = Stats::from_samples(&load_times);
+        print_stats("load", &load_stats);
+        results[facts_dir] = json!({ "load": load_stats.to_json() });
+
+        for (&algorithm, times) in opt.algorithms.iter().zip(&compute_times) {
+            let name = format!("{:?}", algorithm);
+            let stats = Stats::from_samples(times);
+            print_stats(&name, &stats);
+            results[facts_dir][&name] = stats.to_json();
+
+            let baseline_median = baseline
+                .as_ref()
+                .and_then(|baseline| baseline[facts_dir][&name]["median"].as_f64());
+            if let Some(baseline_median) = baseline_median {
+                // a zero median, e.g. from a timer too coarse for the
+                // input, can't be compared against in relative terms
+                if baseline_median <= 0.0 {
+                    println!(
+                        "    baseline median {:0.3}s: not comparable, skipped",
+                        baseline_median
+                    );
+                    continue;
+                }
+
+                let change = (stats.median - baseline_median) / baseline_median * 100.0;
+                let verdict = if change > opt.threshold {
+                    regressions += 1;
+                    "REGRESSION"
+                } else {
+                    "ok"
+                };
+                println!(
+                    "    baseline median {:0.3}s ({:+0.1}%): {}",
+                    baseline_median, change, verdict
+                );
+            }
+        }
+    }
+
+    if let Some(ref path) = opt.save_baseline {
+        serde_json::to_writer_pretty(File::create(path)?, &results)?;
+    }
+
+    if regressions > 0 {
+        Err(failure::err_msg(format!(
+            "{} regression(s) over the {}% threshold",
+            regressions, opt.threshold
+        )))?;
+    }
+
+    Ok(())
+}
+
+fn print_stats(name: &str, stats: &Stats) {
+    println!(
+        "{:<20} min {:0.3}s  median {:0.3}s  mean {:0.3}s  stddev {:0.3}s",
+        format!("{}:", name),
+        stats.min,
+        stats.median,
+        stats.mean,
+        stats.stddev
+    );
+}
+
+fn seconds(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + duration.subsec_nanos() as f64 * 0.000_000_001_f64
+}
@@ -0,0 +1,272 @@
+//! A builder for writing small inputs by hand in tests, with symbolic
+//! names for the regions, loans, points, variables and move paths:
+//!
+//! ```ignore
+//! let (tables, all_facts) = FactsBuilder::new()
+//!     .path(&["a", "b", "c"])
+//!     .borrow_region("'x", "L0", "a")
+//!     .region_live_at("'x", &["b", "c"])
+//!     .invalidates("c", "L0")
+//!     .build();
+//! let output = Output::compute(&all_facts, Algorithm::Naive, false);
+//! assert_errors(&tables, &output, &[("c", "L0")]);
+//! ```
+//!
+//! Point names of the form `Mid(bb0[1])` are parsed as MIR locations,
+//! like the ones emitted by rustc, but any name can be used.
+
+#![cfg(test)]
+
+use crate::facts::{AllFacts, Loan, MovePath, Point, Region, Variable};
+use crate::intern::InternerTables;
+use polonius_engine::{AccessKind, LoanKind, Output};
+
+crate struct FactsBuilder {
+    tables: InternerTables,
+    all_facts: AllFacts,
+}
+
+impl FactsBuilder {
+    crate fn new() -> Self {
+        FactsBuilder {
+            tables: InternerTables::new(),
+            all_facts: AllFacts::default(),
+        }
+    }
+
+    crate fn build(self) -> (InternerTables, AllFacts) {
+        (self.tables, self.all_facts)
+    }
+
+    crate fn universal_region(mut self, region: &str) -> Self {
+        let region = self.region(region);
+        self.all_facts.universal_region.push(region);
+        self
+    }
+
+    crate fn cfg_edge(mut self, from: &str, to: &str) -> Self {
+        let edge = (self.point(from), self.point(to));
+        self.all_facts.cfg_edge.push(edge);
+        self
+    }
+
+    /// Adds an edge between each consecutive pair of `points`.
+    crate fn path(mut self, points: &[&str]) -> Self {
+        for pair in points.windows(2) {
+            self = self.cfg_edge(pair[0], pair[1]);
+        }
+        self
+    }
+
+    crate fn borrow_region(mut self, region: &str, loan: &str, point: &str) -> Self {
+        let fact = (self.region(region), self.loan(loan), self.point(point));
+        self.all_facts.borrow_region.push(fact);
+        self
+    }
+
+    crate fn killed(mut self, loan: &str, point: &str) -> Self {
+        let fact = (self.loan(loan), self.point(point));
+        self.all_facts.killed.push(fact);
+        self
+    }
+
+    crate fn outlives(mut self, region1: &str, region2: &str, point: &str) -> Self {
+        let fact = (self.region(region1), self.region(region2), self.point(point));
+        self.all_facts.outlives.push(fact);
+        self
+    }
+
+    /// Makes `region` live at each of `points`.
+    crate fn region_live_at(mut self, region: &str, points: &[&str]) -> Self {
+        let region = self.region(region);
+        for point in points {
+            let point = self.point(point);
+            self.all_facts.region_live_at.push((region, point));
+        }
+        self
+    }
+
+    crate fn invalidates(mut self, point: &str, loan: &str) -> Self {
+        let fact = (self.point(point), self.loan(loan));
+        self.all_facts.invalidates.push(fact);
+        self
+    }
+
+    crate fn var_defined(mut self, variable: &str, point: &str) -> Self {
+        let fact = (self.variable(variable), self.point(point));
+        self.all_facts.var_defined.push(fact);
+        self
+    }
+
+    crate fn var_used(mut self, variable: &str, point: &str) -> Self {
+        let fact = (self.variable(variable), self.point(point));
+        self.all_facts.var_used.push(fact);
+        self
+    }
+
+    crate fn var_drop_used(mut self, variable: &str, point: &str) -> Self {
+        let fact = (self.variable(variable), self.point(point));
+        self.all_facts.var_drop_used.push(fact);
+        self
+    }
+
+    crate fn var_uses_region(mut self, variable: &str, region: &str) -> Self {
+        let fact = (self.variable(variable), self.region(region));
+        self.all_facts.var_uses_region.push(fact);
+        self
+    }
+
+    crate fn var_drops_region(mut self, variable: &str, region: &str) -> Self {
+        let fact = (self.variable(variable), self.region(region));
+        self.all_facts.var_drops_region.push(fact);
+        self
+    }
+
+    /// Makes `child` a direct child of the move path `parent`.
+    crate fn child(mut self, child: &str, parent: &str) -> Self {
+        let fact = (self.move_path(child), self.move_path(parent));
+        self.all_facts.child.push(fact);
+        self
+    }
+
+    crate fn path_assigned_at(mut self, path: &str, point: &str) -> Self {
+        let fact = (self.move_path(path), self.point(point));
+        self.all_facts.path_assigned_at.push(fact);
+        self
+    }
+
+    crate fn path_moved_at(mut self, path: &str, point: &str) -> Self {
+        let fact = (self.move_path(path), self.point(point));
+        self.all_facts.path_moved_at.push(fact);
+        self
+    }
+
+    crate fn path_accessed_at(mut self, path: &str, point: &str) -> Self {
+        let fact = (self.move_path(path), self.point(point));
+        self.all_facts.path_accessed_at.push(fact);
+        self
+    }
+
+    crate fn loan_path(mut self, loan: &str, path: &str) -> Self {
+        let fact = (self.loan(loan), self.move_path(path));
+        self.all_facts.loan_path.push(fact);
+        self
+    }
+
+    crate fn loan_reserved_at(mut self, loan: &str, point: &str) -> Self {
+        let fact = (self.loan(loan), self.point(point));
+        self.all_facts.loan_reserved_at.push(fact);
+        self
+    }
+
+    crate fn loan_activated_at(mut self, loan: &str, point: &str) -> Self {
+        let fact = (self.loan(loan), self.point(point));
+        self.all_facts.loan_activated_at.push(fact);
+        self
+    }
+
+    /// Adds both `invalidates(point, loan)` and `invalidates_by_read(point, loan)`.
+    crate fn invalidates_by_read(mut self, point: &str, loan: &str) -> Self {
+        let fact = (self.point(point), self.loan(loan));
+        self.all_facts.invalidates.push(fact);
+        self.all_facts.invalidates_by_read.push(fact);
+        self
+    }
+
+    crate fn invalidates_by_access(
+        mut self,
+        point: &str,
+        loan: &str,
+        access: AccessKind,
+        kind: LoanKind,
+    ) -> Self {
+        let fact = (self.point(point), self.loan(loan), access, kind);
+        self.all_facts.invalidates_by_access.push(fact);
+        self
+    }
+
+    fn region(&mut self, name: &str) -> Region {
+        self.tables.regions.intern(name)
+    }
+
+    fn loan(&mut self, name: &str) -> Loan {
+        self.tables.loans.intern(name)
+    }
+
+    fn point(&mut self, name: &str) -> Point {
+        self.tables.points.intern(name)
+    }
+
+    fn variable(&mut self, name: &str) -> Variable {
+        self.tables.variables.intern(name)
+    }
+
+    fn move_path(&mut self, name: &str) -> MovePath {
+        self.tables.paths.intern(name)
+    }
+}
+
+/// Asserts that the `potential_errors` of `output` are exactly the
+/// `(point, loan)` pairs named in `expected`, in any order.
+crate fn assert_errors(
+    tables: &InternerTables,
+    output: &Output<Region, Loan, Point, MovePath>,
+    expected: &[(&str, &str)],
+) {
+    let mut expected: Vec<(String, String)> = expected
+        .iter()
+        .map(|&(point, loan)| (point.to_string(), loan.to_string()))
+        .collect();
+    expected.sort();
+    assert_eq!(errors_by_name(tables, output), expected);
+}
+
+/// The `potential_errors` of `output`, as sorted `(point, loan)` names.
+crate fn errors_by_name(
+    tables: &InternerTables,
+    output: &Output<Region, Loan, Point, MovePath>,
+) -> Vec<(String, String)> {
+    let mut errors: Vec<_> = output
+        .potential_errors
+        .iter()
+        .flat_map(|(&point, loans)| {
+            loans.iter().map(move |&loan| {
+                (
+                    tables.points.untern(point).to_string(),
+                    tables.loans.untern(loan).to_string(),
+                )
+            })
+        })
+        .collect();
+    errors.sort();
+    errors
+}
+
+/// Asserts that the `move_errors` of `output` are exactly the
+/// `(point, path)` pairs named in `expected`, in any order.
+crate fn assert_move_errors(
+    tables: &InternerTables,
+    output: &Output<Region, Loan, Point, MovePath>,
+    expected: &[(&str, &str)],
+) {
+    let mut expected: Vec<(String, String)> = expected
+        .iter()
+        .map(|&(point, path)| (point.to_string(), path.to_string()))
+        .collect();
+    expected.sort();
+
+    let mut errors: Vec<_> = output
+        .move_errors
+        .iter()
+        .flat_map(|(&point, paths)| {
+            paths.iter().map(move |&path| {
+                (
+                    tables.points.untern(point).to_string(),
+                    tables.paths.untern(path).to_string(),
+                )
+            })
+        })
+        .collect();
+    errors.sort();
+    assert_eq!(errors, expected);
+}
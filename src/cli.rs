@@ -1,26 +1,30 @@
-#![allow(deprecated)] // arg_enum! uses deprecated stuff
-
-use crate::dump;
-use crate::facts::{Loan, Point, Region};
+use crate::bench::{self, BenchOpt};
+use crate::binary;
+use crate::compare;
+use crate::dump::{self, OutputFormat};
+use crate::explain;
+use crate::facts::{AllFacts, Loan, MovePath, Point, Region};
+use crate::graphviz;
 use crate::intern;
-use crate::output::Output;
+use crate::reduce::{self, ReduceOpt};
+use crate::stats::{self, StatsOpt};
+use crate::output::tracking::RegionDegrees;
 use crate::tab_delim;
+use crate::validate;
 use failure::Error;
-use std::path::Path;
+use polonius_engine::{Algorithm, Output};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
+use structopt::clap::AppSettings;
 use structopt::StructOpt;
 
-arg_enum! {
-    #[derive(Debug, Clone, Copy)]
-    pub enum Algorithm {
-        Naive,
-        DatafrogOpt,
-        LocationInsensitive,
-    }
-}
-
 #[derive(StructOpt, Debug)]
-#[structopt(name = "borrow-check")]
+#[structopt(
+    name = "borrow-check",
+    raw(setting = "AppSettings::SubcommandsNegateReqs")
+)]
 pub struct Opt {
     #[structopt(
         short = "a",
@@ -28,44 +32,213 @@ pub struct Opt {
         raw(possible_values = "&Algorithm::variants()", case_insensitive = "true")
     )]
     algorithm: Algorithm,
+    /// Run each of these algorithms and report where their outputs differ
+    #[structopt(
+        long = "compare",
+        raw(
+            use_delimiter = "true",
+            possible_values = "&Algorithm::variants()",
+            case_insensitive = "true"
+        )
+    )]
+    compare: Vec<Algorithm>,
+    /// Fail instead of warning if `cfg_edge` or `region_live_at` facts are missing
+    #[structopt(long = "require-core-facts")]
+    require_core_facts: bool,
+    /// Derive `killed` from the `loan_path`, `child` and `path_assigned_at`
+    /// facts, instead of using the `killed` facts
+    #[structopt(long = "derive-killed")]
+    derive_killed: bool,
     #[structopt(long = "skip-tuples")]
     skip_tuples: bool,
     #[structopt(long = "skip-timing")]
     skip_timing: bool,
     #[structopt(short = "v")]
     verbose: bool,
+    /// Explain why each potential error is live where it is invalidated
+    #[structopt(long = "explain")]
+    explain: bool,
+    /// Instrument the rules of the DatafrogOpt analysis and print the
+    /// tuples and time of each one
+    #[structopt(long = "profile")]
+    profile: bool,
+    /// Print statistics on the degrees of the regions in the subset
+    /// relation at each point (only with `-a Naive` or `-a DatafrogOpt`)
+    #[structopt(long = "stats")]
+    stats: bool,
+    /// Write the CFG of each directory, annotated with the output, to this graphviz file
+    #[structopt(long = "graphviz")]
+    graphviz_file: Option<String>,
     #[structopt(short = "o", long = "output")]
     output_directory: Option<String>,
+    #[structopt(
+        long = "format",
+        default_value = "text",
+        raw(possible_values = "&OutputFormat::variants()", case_insensitive = "true")
+    )]
+    format: OutputFormat,
     #[structopt(raw(required = "true"))]
     fact_dirs: Vec<String>,
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(StructOpt, Debug)]
+enum Command {
+    /// Convert a tab-delimited fact directory to the binary format,
+    /// which is used instead of the `.facts` files when present
+    #[structopt(name = "convert")]
+    Convert {
+        /// Convert a fact directory (in either format) to tab-delimited
+        /// `.facts` files instead
+        #[structopt(long = "tab-delimited")]
+        tab_delimited: bool,
+        input_dir: String,
+        /// Defaults to the input directory
+        output_dir: Option<String>,
+    },
+
+    /// Run the algorithms repeatedly and report timing statistics
+    #[structopt(name = "bench")]
+    Bench(BenchOpt),
+
+    /// Print metrics on the shape of the inputs, without running any analysis
+    #[structopt(name = "stats")]
+    Stats(StatsOpt),
+
+    /// Check that the facts are consistent, e.g. that they only mention
+    /// points of the CFG and loans that are created
+    #[structopt(name = "validate")]
+    Validate {
+        #[structopt(raw(required = "true"))]
+        fact_dirs: Vec<String>,
+    },
+
+    /// Minimize a tab-delimited fact directory while two algorithms
+    /// disagree on it (or while an error is reported), and write the
+    /// result to another directory
+    #[structopt(name = "reduce")]
+    Reduce(ReduceOpt),
 }
 
 pub fn main(opt: Opt) -> Result<(), Error> {
+    if let Some(ref command) = opt.command {
+        return command_main(command);
+    }
+
+    if !opt.compare.is_empty() {
+        return compare_main(opt);
+    }
+
+    if opt.profile && opt.algorithm != Algorithm::DatafrogOpt {
+        Err(failure::err_msg("`--profile` is only supported by `-a DatafrogOpt`"))?;
+    }
+
+    // `Hybrid` only computes the subset relation for some loans, if any
+    let complete_subset = match opt.algorithm {
+        Algorithm::Naive | Algorithm::DatafrogOpt => true,
+        Algorithm::LocationInsensitive | Algorithm::Hybrid => false,
+    };
+    if opt.stats && !complete_subset {
+        Err(failure::err_msg(
+            "`--stats` needs the complete location-sensitive subset relation, \
+             use `-a Naive` or `-a DatafrogOpt`",
+        ))?;
+    }
+
     do catch {
         let output_directory = opt.output_directory.map(|x| Path::new(&x).to_owned());
+        let mut json_results = vec![];
+        let mut graphviz_file = match opt.graphviz_file {
+            Some(ref path) => Some(File::create(path)?),
+            None => None,
+        };
         for facts_dir in opt.fact_dirs {
             let tables = &mut intern::InternerTables::new();
 
-            let result: Result<(Duration, Output<Region, Loan, Point>), Error> = do catch {
-                let verbose = opt.verbose;
+            let result: Result<(Duration, AllFacts, Output<Region, Loan, Point, MovePath>), Error> = do catch {
+                // `--stats` needs the `subset` relation, which is only kept
+                // along with the other debugging relations
+                let dump_enabled = opt.verbose || opt.stats;
                 let algorithm = opt.algorithm;
-                let all_facts =
-                    tab_delim::load_tab_delimited_facts(tables, &Path::new(&facts_dir))?;
-                timed(|| Output::compute(&all_facts, algorithm, verbose))
+                let mut all_facts =
+                    load_facts(tables, &Path::new(&facts_dir), opt.require_core_facts)?;
+                if opt.derive_killed {
+                    all_facts.killed = polonius_engine::derive_killed(&all_facts);
+                }
+                let (duration, output) = if opt.profile {
+                    timed(|| Output::compute_profiled(&all_facts, dump_enabled))
+                } else {
+                    timed(|| Output::compute(&all_facts, algorithm, dump_enabled))
+                };
+                (duration, all_facts, output)
             };
 
             match result {
-                Ok((duration, output)) => {
-                    println!("--------------------------------------------------");
-                    println!("Directory: {}", facts_dir);
-                    if !opt.skip_timing {
-                        let seconds: f64 = duration.as_secs() as f64;
-                        let millis: f64 = duration.subsec_nanos() as f64 * 0.000_000_001_f64;
-                        println!("Time: {:0.3}s", seconds + millis);
+                Ok((duration, all_facts, mut output)) => {
+                    let seconds: f64 = duration.as_secs() as f64;
+                    let millis: f64 = duration.subsec_nanos() as f64 * 0.000_000_001_f64;
+
+                    let degrees = if opt.stats {
+                        Some(RegionDegrees::from_subset(&output.subset))
+                    } else {
+                        None
+                    };
+
+                    // only show the debugging relations when asked to
+                    output.dump_enabled = opt.verbose;
+
+                    if let Some(ref profile) = output.profile {
+                        eprintln!("Profile of `{}`:\n{}", facts_dir, profile);
                     }
-                    if !opt.skip_tuples {
-                        dump::dump_output(&output, &output_directory, tables)
-                            .expect("Failed to write output");
+
+                    if let Some(ref mut file) = graphviz_file {
+                        graphviz::dump_graphviz(&facts_dir, &all_facts, &output, tables, file)
+                            .expect("Failed to write graphviz output");
+                    }
+
+                    match opt.format {
+                        OutputFormat::Text => {
+                            println!("--------------------------------------------------");
+                            println!("Directory: {}", facts_dir);
+                            if !opt.skip_timing {
+                                println!("Time: {:0.3}s", seconds + millis);
+                            }
+                            if !opt.skip_tuples {
+                                dump::dump_output(&output, &output_directory, tables)
+                                    .expect("Failed to write output");
+                            }
+                            if opt.explain {
+                                explain::explain_errors(
+                                    &all_facts,
+                                    &output,
+                                    tables,
+                                    &mut io::stdout(),
+                                ).expect("Failed to write explanations");
+                            }
+                            if let Some(ref degrees) = degrees {
+                                degrees
+                                    .dump(&mut io::stdout())
+                                    .expect("Failed to write statistics");
+                            }
+                        }
+
+                        OutputFormat::Json => {
+                            let mut result = json!({
+                                "directory": facts_dir,
+                                "algorithm": format!("{:?}", opt.algorithm),
+                            });
+                            if !opt.skip_timing {
+                                result["time"] = json!(seconds + millis);
+                            }
+                            if !opt.skip_tuples {
+                                result["relations"] = dump::output_to_json(&output, tables);
+                            }
+                            if let Some(ref degrees) = degrees {
+                                result["subset_stats"] = degrees.to_json();
+                            }
+                            json_results.push(result);
+                        }
                     }
                 }
 
@@ -74,10 +247,164 @@ pub fn main(opt: Opt) -> Result<(), Error> {
                 }
             }
         }
+
+        if opt.format == OutputFormat::Json {
+            dump::dump_json(&json!(json_results), &output_directory)
+                .expect("Failed to write output");
+        }
+    }
+}
+
+fn compare_main(opt: Opt) -> Result<(), Error> {
+    do catch {
+        let mut all_agree = true;
+        for facts_dir in &opt.fact_dirs {
+            let tables = &mut intern::InternerTables::new();
+
+            let result: Result<Vec<_>, Error> = do catch {
+                let mut all_facts =
+                    load_facts(tables, &Path::new(facts_dir), opt.require_core_facts)?;
+                if opt.derive_killed {
+                    all_facts.killed = polonius_engine::derive_killed(&all_facts);
+                }
+                opt.compare
+                    .iter()
+                    .map(|&algorithm| {
+                        let output = Output::compute(&all_facts, algorithm, opt.verbose);
+                        (algorithm, output)
+                    })
+                    .collect()
+            };
+
+            println!("--------------------------------------------------");
+            println!("Directory: {}", facts_dir);
+            match result {
+                Ok(outputs) => {
+                    let (baseline, rest) = outputs.split_first().unwrap();
+                    for (algorithm, output) in rest {
+                        let agree = compare::compare_outputs(
+                            (baseline.0, &baseline.1),
+                            (*algorithm, output),
+                            Some(tables),
+                        );
+                        all_agree &= agree;
+                    }
+                }
+
+                Err(error) => {
+                    eprintln!("`{}`: {}", facts_dir, error);
+                }
+            }
+        }
+
+        if !all_agree {
+            Err(failure::err_msg("algorithms disagree"))?;
+        }
+    }
+}
+
+fn command_main(command: &Command) -> Result<(), Error> {
+    match command {
+        Command::Convert {
+            tab_delimited,
+            input_dir,
+            output_dir,
+        } => {
+            let input_dir = Path::new(input_dir);
+            let output_dir = output_dir.as_ref().map_or(input_dir, |dir| Path::new(dir));
+            let tables = &mut intern::InternerTables::new();
+            if *tab_delimited {
+                let all_facts = load_facts(tables, input_dir, false)?;
+                tab_delim::write_tab_delimited_facts(tables, &all_facts, output_dir)?;
+            } else {
+                let all_facts = tab_delim::load_tab_delimited_facts(tables, input_dir)?;
+                binary::write_binary_facts(tables, &all_facts, output_dir)?;
+            }
+            Ok(())
+        }
+
+        Command::Bench(bench_opt) => bench::bench(bench_opt),
+
+        Command::Stats(stats_opt) => stats::stats(stats_opt),
+
+        Command::Validate { fact_dirs } => validate_main(fact_dirs),
+
+        Command::Reduce(reduce_opt) => reduce::reduce_main(reduce_opt),
+    }
+}
+
+fn validate_main(fact_dirs: &[String]) -> Result<(), Error> {
+    let mut problem_count = 0;
+    for facts_dir in fact_dirs {
+        let tables = &mut intern::InternerTables::new();
+
+        println!("--------------------------------------------------");
+        println!("Directory: {}", facts_dir);
+        let all_facts = match load_facts(tables, Path::new(facts_dir), false) {
+            Ok(all_facts) => all_facts,
+            Err(error) => {
+                // an input that can't be loaded is a problem too
+                eprintln!("`{}`: {}", facts_dir, error);
+                problem_count += 1;
+                continue;
+            }
+        };
+
+        let problems = validate::validate(&all_facts, tables);
+        for problem in &problems {
+            println!("    {}", problem.describe(tables));
+        }
+        problem_count += problems.len();
+    }
+
+    if problem_count > 0 {
+        Err(failure::err_msg(format!("{} problem(s) found", problem_count)))?;
+    }
+
+    Ok(())
+}
+
+/// Loads the facts in `facts_dir`, from the binary format if it has
+/// been converted, unless some `.facts` file was modified since.
+crate fn load_facts(
+    tables: &mut intern::InternerTables,
+    facts_dir: &Path,
+    require_core_facts: bool,
+) -> Result<AllFacts, Error> {
+    let binary_file = facts_dir.join(binary::BINARY_FACTS_FILE);
+    if binary_file.exists() {
+        match newer_facts_file(facts_dir, &binary_file)? {
+            None => return Ok(binary::load_binary_facts(tables, facts_dir)?),
+            Some(facts_file) => eprintln!(
+                "warning: `{}` is newer than `{}`, loading the `.facts` files instead; \
+                 run `convert` again to update it",
+                facts_file.display(),
+                binary_file.display()
+            ),
+        }
+    }
+
+    if require_core_facts {
+        tab_delim::check_core_facts(facts_dir)?;
+    }
+    Ok(tab_delim::load_tab_delimited_facts(tables, facts_dir)?)
+}
+
+/// A `.facts` file in `facts_dir` modified after `binary_file`, if any.
+fn newer_facts_file(facts_dir: &Path, binary_file: &Path) -> io::Result<Option<PathBuf>> {
+    let converted = fs::metadata(binary_file)?.modified()?;
+    for entry in fs::read_dir(facts_dir)? {
+        let path = entry?.path();
+        if path.extension().map_or(false, |extension| extension == "facts")
+            && fs::metadata(&path)?.modified()? > converted
+        {
+            return Ok(Some(path));
+        }
     }
+    Ok(None)
 }
 
-fn timed<T>(op: impl FnOnce() -> T) -> (Duration, T) {
+crate fn timed<T>(op: impl FnOnce() -> T) -> (Duration, T) {
     let start = Instant::now();
     let output = op();
     let duration = start.elapsed();
@@ -0,0 +1,100 @@
+use crate::dump::Atom;
+use crate::facts::{Loan, MovePath, Point, Region};
+use crate::intern::InternerTables;
+use fxhash::FxHashMap;
+use polonius_engine::{Algorithm, Output};
+use std::collections::BTreeSet;
+
+/// Whether the outputs of both algorithms are expected to have the
+/// same `borrow_live_at`: `LocationInsensitive` only computes an
+/// over-approximation of it, and only with `-v`, and `Hybrid` only
+/// for some loans. Otherwise only the errors are compared.
+fn compares_borrow_live_at(algorithm1: Algorithm, algorithm2: Algorithm) -> bool {
+    let exact = |algorithm| match algorithm {
+        Algorithm::Naive | Algorithm::DatafrogOpt => true,
+        Algorithm::LocationInsensitive | Algorithm::Hybrid => false,
+    };
+    exact(algorithm1) && exact(algorithm2)
+}
+
+/// Compares the results of two algorithms, relation by relation,
+/// and, given the `tables` to name them, prints the tuples that only
+/// one of them derived. Returns `true` if the outputs agree.
+crate fn compare_outputs(
+    (algorithm1, output1): (Algorithm, &Output<Region, Loan, Point, MovePath>),
+    (algorithm2, output2): (Algorithm, &Output<Region, Loan, Point, MovePath>),
+    tables: Option<&InternerTables>,
+) -> bool {
+    let mut agree = true;
+    let algorithms = (algorithm1, algorithm2);
+
+    if compares_borrow_live_at(algorithm1, algorithm2) {
+        agree &= compare_relation(
+            algorithms,
+            "borrow_live_at",
+            (&output1.borrow_live_at, &output2.borrow_live_at),
+            tables,
+        );
+    }
+    agree &= compare_relation(
+        algorithms,
+        "potential_errors",
+        (&output1.potential_errors, &output2.potential_errors),
+        tables,
+    );
+    agree &= compare_relation(
+        algorithms,
+        "move_errors",
+        (&output1.move_errors, &output2.move_errors),
+        tables,
+    );
+
+    agree
+}
+
+/// Prints the tuples of the relation `name` that only one of the
+/// algorithms derived, if there are `tables`, and returns `true` if
+/// there are none.
+fn compare_relation<T: Atom + Ord>(
+    (algorithm1, algorithm2): (Algorithm, Algorithm),
+    name: &str,
+    (relation1, relation2): (&FxHashMap<Point, Vec<T>>, &FxHashMap<Point, Vec<T>>),
+    tables: Option<&InternerTables>,
+) -> bool {
+    let tuples1 = point_tuples(relation1);
+    let tuples2 = point_tuples(relation2);
+    if tuples1 == tuples2 {
+        return true;
+    }
+    let tables = match tables {
+        Some(tables) => tables,
+        None => return false,
+    };
+
+    println!("{:?} and {:?} disagree on `{}`", algorithm1, algorithm2, name);
+    let differences = [
+        (algorithm1, tuples1.difference(&tuples2)),
+        (algorithm2, tuples2.difference(&tuples1)),
+    ];
+    for (algorithm, tuples) in differences.iter().cloned() {
+        let mut tuples: Vec<_> = tuples.cloned().collect();
+        tuples.sort_by_key(|&(point, atom)| (tables.point_location(point), point, atom));
+        for (point, atom) in tuples {
+            println!(
+                "    only in {:?}: {}\t{}",
+                algorithm,
+                tables.points.untern(point),
+                T::table(tables).untern(atom),
+            );
+        }
+    }
+
+    false
+}
+
+fn point_tuples<T: Copy + Ord>(relation: &FxHashMap<Point, Vec<T>>) -> BTreeSet<(Point, T)> {
+    relation
+        .iter()
+        .flat_map(|(&point, atoms)| atoms.iter().map(move |&atom| (point, atom)))
+        .collect()
+}
@@ -1,15 +1,39 @@
 use crate::facts::*;
 use crate::intern::InternerTables;
 use crate::intern::*;
-use crate::output::Output;
 use fxhash::FxHashMap;
+use polonius_engine::{AccessKind, LoanKind, Output};
+use serde_json::{self, Map, Value};
 use std::collections::{BTreeMap, BTreeSet};
 use std::hash::Hash;
 use std::io::{self, Write};
 use std::path::PathBuf;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+crate enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl OutputFormat {
+    crate fn variants() -> [&'static str; 2] {
+        ["Text", "Json"]
+    }
+}
+
+impl ::std::str::FromStr for OutputFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_ref() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(String::from("valid values: Text, Json")),
+        }
+    }
+}
+
 crate fn dump_output(
-    output: &Output<Region, Loan, Point>,
+    output: &Output<Region, Loan, Point, MovePath>,
     output_dir: &Option<PathBuf>,
     intern: &InternerTables,
 ) -> io::Result<()> {
@@ -45,6 +69,11 @@ crate fn dump_output(
             intern,
             &output.potential_errors,
         )?;
+        dump_rows(
+            &mut writer_for(output_dir, "potential_error_kinds")?,
+            intern,
+            &output.potential_error_kinds,
+        )?;
         dump_rows(
             &mut writer_for(output_dir, "subset")?,
             intern,
@@ -55,6 +84,21 @@ crate fn dump_output(
             intern,
             &output.subset_anywhere,
         )?;
+        dump_rows(
+            &mut writer_for(output_dir, "move_errors")?,
+            intern,
+            &output.move_errors,
+        )?;
+        dump_rows(
+            &mut writer_for(output_dir, "maybe_initialized")?,
+            intern,
+            &output.maybe_initialized,
+        )?;
+        dump_rows(
+            &mut writer_for(output_dir, "maybe_moved")?,
+            intern,
+            &output.maybe_moved,
+        )?;
     }
     return Ok(());
 
@@ -79,6 +123,106 @@ crate fn dump_output(
     }
 }
 
+/// Converts the relations of `output` into a JSON object mapping each
+/// relation name to its list of tuples, with the atoms uninterned.
+crate fn output_to_json(
+    output: &Output<Region, Loan, Point, MovePath>,
+    intern: &InternerTables,
+) -> Value {
+    let mut relations = Map::new();
+    relations.insert(
+        "borrow_live_at".to_string(),
+        json_rows(intern, &output.borrow_live_at),
+    );
+    relations.insert(
+        "potential_errors".to_string(),
+        json_rows(intern, &output.potential_errors),
+    );
+    relations.insert(
+        "potential_error_kinds".to_string(),
+        json_rows(intern, &output.potential_error_kinds),
+    );
+    relations.insert(
+        "move_errors".to_string(),
+        json_rows(intern, &output.move_errors),
+    );
+
+    // the intermediate relations, only kept for debugging
+    if output.dump_enabled {
+        relations.insert("restricts".to_string(), json_rows(intern, &output.restricts));
+        relations.insert(
+            "restricts_anywhere".to_string(),
+            json_rows(intern, &output.restricts_anywhere),
+        );
+        relations.insert(
+            "region_live_at".to_string(),
+            json_rows(intern, &output.region_live_at),
+        );
+        relations.insert(
+            "invalidates".to_string(),
+            json_rows(intern, &output.invalidates),
+        );
+        relations.insert("subset".to_string(), json_rows(intern, &output.subset));
+        relations.insert(
+            "subset_anywhere".to_string(),
+            json_rows(intern, &output.subset_anywhere),
+        );
+        relations.insert(
+            "maybe_initialized".to_string(),
+            json_rows(intern, &output.maybe_initialized),
+        );
+        relations.insert(
+            "maybe_moved".to_string(),
+            json_rows(intern, &output.maybe_moved),
+        );
+    }
+
+    Value::Object(relations)
+}
+
+/// Writes `document` to `output.json` in the output directory, or to
+/// stdout if there is none.
+crate fn dump_json(document: &Value, output_dir: &Option<PathBuf>) -> io::Result<()> {
+    use std::fs;
+
+    match output_dir {
+        Some(dir) => {
+            fs::create_dir_all(&dir)?;
+            let file = fs::File::create(dir.join("output.json"))?;
+            serde_json::to_writer_pretty(file, document)?;
+        }
+        None => {
+            let stdout = io::stdout();
+            let mut stdout = stdout.lock();
+            serde_json::to_writer_pretty(&mut stdout, document)?;
+            writeln!(stdout)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn json_rows(intern: &InternerTables, value: &impl OutputDump) -> Value {
+    let mut rows = Vec::new();
+    OutputDump::push_all(value, intern, &mut vec![], &mut rows);
+    Value::Array(
+        rows.into_iter()
+            .map(|row| Value::Array(row.into_iter().map(json_atom).collect()))
+            .collect(),
+    )
+}
+
+/// The interned names are the quoted strings found in the input
+/// facts; remove the quotes so they don't get escaped.
+fn json_atom(name: &str) -> Value {
+    let name = if name.len() >= 2 && name.starts_with('"') && name.ends_with('"') {
+        &name[1..name.len() - 1]
+    } else {
+        name
+    };
+    Value::String(name.to_string())
+}
+
 trait OutputDump {
     fn push_all(
         &'a self,
@@ -133,7 +277,7 @@ where
     ) {
         let table = K::table(intern);
         let mut keys: Vec<_> = self.keys().collect();
-        keys.sort();
+        keys.sort_by_key(|&&key| (key.location(intern), key));
         for key in keys {
             preserve(prefix, |prefix| {
                 prefix.push(table.untern(*key));
@@ -158,7 +302,7 @@ where
     ) {
         let table = K::table(intern);
         let mut keys: Vec<_> = self.keys().collect();
-        keys.sort();
+        keys.sort_by_key(|&&key| (key.location(intern), key));
         for key in keys {
             preserve(prefix, |prefix| {
                 prefix.push(table.untern(*key));
@@ -218,6 +362,22 @@ impl<T: Atom> OutputDump for T {
     }
 }
 
+/// The kinds of a conflicting access and loan, as two columns.
+impl OutputDump for (AccessKind, LoanKind) {
+    fn push_all(
+        &'a self,
+        _intern: &'a InternerTables,
+        prefix: &mut Vec<&'a str>,
+        output: &mut Vec<Vec<&'a str>>,
+    ) {
+        preserve(prefix, |prefix| {
+            prefix.push(self.0.name());
+            prefix.push(self.1.name());
+            output.push(prefix.clone());
+        });
+    }
+}
+
 fn preserve<'a>(s: &mut Vec<&'a str>, op: impl FnOnce(&mut Vec<&'a str>)) {
     let len = s.len();
     op(s);
@@ -226,6 +386,11 @@ fn preserve<'a>(s: &mut Vec<&'a str>, op: impl FnOnce(&mut Vec<&'a str>)) {
 
 crate trait Atom: Copy + From<usize> + Into<usize> {
     fn table(intern: &InternerTables) -> &Interner<Self>;
+
+    /// Used to sort points by MIR location rather than by intern order.
+    fn location(self, _intern: &InternerTables) -> Option<Location> {
+        None
+    }
 }
 
 impl Atom for Region {
@@ -238,6 +403,10 @@ impl Atom for Point {
     fn table(intern: &InternerTables) -> &Interner<Self> {
         &intern.points
     }
+
+    fn location(self, intern: &InternerTables) -> Option<Location> {
+        intern.point_location(self)
+    }
 }
 
 impl Atom for Loan {
@@ -245,3 +414,9 @@ impl Atom for Loan {
         &intern.loans
     }
 }
+
+impl Atom for MovePath {
+    fn table(intern: &InternerTables) -> &Interner<Self> {
+        &intern.paths
+    }
+}
@@ -0,0 +1,270 @@
+//! Reconstructs, for an error `(B, P)`, a derivation explaining why
+//! the loan `B` is live at the point `P` where it is invalidated.
+//!
+//! This follows the rules of the `Naive` analysis: a region requires
+//! the loan from the point where it is issued, passes it on to its
+//! supersets, and carries it along CFG edges where it is live and
+//! the loan is not killed.
+
+use crate::facts::{AllFacts, Loan, MovePath, Point, Region};
+use crate::intern::InternerTables;
+use fxhash::{FxHashMap, FxHashSet};
+use polonius_engine::{AccessKind, Algorithm, Output};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::io::{self, Write};
+
+/// One step of a derivation of `requires(R, B, P)`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+crate enum Step {
+    /// `borrow_region(region, B, point)`: the loan is issued into `region`
+    Borrow { region: Region, point: Point },
+
+    /// `subset(from, to, point)`: `to` requires whatever `from` requires
+    Subset { from: Region, to: Region, point: Point },
+
+    /// `cfg_edge(from, to)`: `region` is live in `to` and carries the
+    /// loan across the edge
+    Flow { region: Region, from: Point, to: Point },
+}
+
+crate struct Explainer<'facts> {
+    all_facts: &'facts AllFacts,
+    subset: FxHashMap<Point, BTreeMap<Region, BTreeSet<Region>>>,
+    region_live_at: FxHashSet<(Region, Point)>,
+    killed: FxHashSet<(Loan, Point)>,
+    successors: FxHashMap<Point, Vec<Point>>,
+}
+
+impl Explainer<'facts> {
+    crate fn new(all_facts: &'facts AllFacts) -> Self {
+        // The location-sensitive `subset` relation, closed at each point,
+        // and the live regions, including the universal ones and the ones
+        // computed from the variable facts.
+        let output = Output::compute(all_facts, Algorithm::Naive, true);
+        let region_live_at = output
+            .region_live_at
+            .iter()
+            .flat_map(|(&p, regions)| regions.iter().map(move |&r| (r, p)))
+            .collect();
+
+        let mut successors = FxHashMap::default();
+        for &(p, q) in &all_facts.cfg_edge {
+            successors.entry(p).or_insert(Vec::new()).push(q);
+        }
+
+        Explainer {
+            all_facts,
+            subset: output.subset,
+            region_live_at,
+            killed: all_facts.killed.iter().cloned().collect(),
+            successors,
+        }
+    }
+
+    /// Finds a shortest derivation of `borrow_live_at(loan, point)`,
+    /// or `None` if there is none (e.g. if the error was reported by a
+    /// location-insensitive analysis and is spurious).
+    crate fn explain(&self, loan: Loan, point: Point) -> Option<Vec<Step>> {
+        let mut parents: FxHashMap<(Region, Point), Step> = FxHashMap::default();
+        let mut queue = VecDeque::new();
+
+        for &(region, borrow, origin) in &self.all_facts.borrow_region {
+            if borrow == loan && !parents.contains_key(&(region, origin)) {
+                parents.insert((region, origin), Step::Borrow { region, point: origin });
+                queue.push_back((region, origin));
+            }
+        }
+
+        while let Some((region, p)) = queue.pop_front() {
+            if p == point && self.region_live_at.contains(&(region, p)) {
+                return Some(self.derivation(&parents, (region, p)));
+            }
+
+            if let Some(supersets) = self.subset.get(&p).and_then(|map| map.get(&region)) {
+                for &superset in supersets {
+                    if !parents.contains_key(&(superset, p)) {
+                        let step = Step::Subset { from: region, to: superset, point: p };
+                        parents.insert((superset, p), step);
+                        queue.push_back((superset, p));
+                    }
+                }
+            }
+
+            if self.killed.contains(&(loan, p)) {
+                continue;
+            }
+
+            for &q in self.successors.get(&p).map(|v| &v[..]).unwrap_or(&[]) {
+                if self.region_live_at.contains(&(region, q)) && !parents.contains_key(&(region, q))
+                {
+                    parents.insert((region, q), Step::Flow { region, from: p, to: q });
+                    queue.push_back((region, q));
+                }
+            }
+        }
+
+        None
+    }
+
+    fn derivation(
+        &self,
+        parents: &FxHashMap<(Region, Point), Step>,
+        mut current: (Region, Point),
+    ) -> Vec<Step> {
+        let mut steps = vec![];
+        loop {
+            let step = parents[&current];
+            steps.push(step);
+            current = match step {
+                Step::Borrow { .. } => break,
+                Step::Subset { from, point, .. } => (from, point),
+                Step::Flow { region, from, .. } => (region, from),
+            };
+        }
+        steps.reverse();
+        steps
+    }
+}
+
+/// Prints an explanation for each of the `potential_errors` of `output`.
+crate fn explain_errors(
+    all_facts: &AllFacts,
+    output: &Output<Region, Loan, Point, MovePath>,
+    tables: &InternerTables,
+    stream: &mut dyn Write,
+) -> io::Result<()> {
+    let explainer = Explainer::new(all_facts);
+
+    let mut errors: Vec<(Point, Loan)> = output
+        .potential_errors
+        .iter()
+        .flat_map(|(&point, loans)| loans.iter().map(move |&loan| (point, loan)))
+        .collect();
+    errors.sort_by_key(|&(point, loan)| (tables.point_location(point), point, loan));
+
+    for (point, loan) in errors {
+        let point_name = tables.points.untern(point);
+        let loan_name = tables.loans.untern(loan);
+        writeln!(
+            stream,
+            "error: {} is invalidated at {} while it is live",
+            loan_name, point_name
+        )?;
+
+        let steps = match explainer.explain(loan, point) {
+            Some(steps) => steps,
+            None => {
+                writeln!(stream, "    no location-sensitive derivation found\n")?;
+                continue;
+            }
+        };
+
+        for step in &steps {
+            match *step {
+                Step::Borrow { region, point } => writeln!(
+                    stream,
+                    "    borrow_region({}, {}, {})",
+                    tables.regions.untern(region),
+                    loan_name,
+                    tables.points.untern(point),
+                )?,
+                Step::Subset { from, to, point } => writeln!(
+                    stream,
+                    "    subset({}, {}, {})",
+                    tables.regions.untern(from),
+                    tables.regions.untern(to),
+                    tables.points.untern(point),
+                )?,
+                Step::Flow { region, from, to } => writeln!(
+                    stream,
+                    "    cfg_edge({}, {}) with region_live_at({}, {})",
+                    tables.points.untern(from),
+                    tables.points.untern(to),
+                    tables.regions.untern(region),
+                    tables.points.untern(to),
+                )?,
+            }
+        }
+
+        let region = match steps.last() {
+            Some(&Step::Borrow { region, .. })
+            | Some(&Step::Subset { to: region, .. })
+            | Some(&Step::Flow { region, .. }) => region,
+            None => unreachable!(),
+        };
+        writeln!(
+            stream,
+            "    region_live_at({}, {})",
+            tables.regions.untern(region),
+            point_name
+        )?;
+        for conflict in conflicts(all_facts, output, tables, point, loan) {
+            writeln!(stream, "    {}", conflict)?;
+        }
+        writeln!(stream)?;
+    }
+
+    Ok(())
+}
+
+/// The input facts by which `loan` is invalidated at `point`: the
+/// `invalidates` or `invalidates_by_read` fact, or the conflicting
+/// `invalidates_by_access` ones with their kinds, along with the
+/// reservation of a two-phase loan, which only reads conflict with
+/// once it is activated.
+fn conflicts(
+    all_facts: &AllFacts,
+    output: &Output<Region, Loan, Point, MovePath>,
+    tables: &InternerTables,
+    point: Point,
+    loan: Loan,
+) -> Vec<String> {
+    let point_name = tables.points.untern(point);
+    let loan_name = tables.loans.untern(loan);
+    let fact_name = |relation: &str, point: Point| {
+        format!("{}({}, {})", relation, loan_name, tables.points.untern(point))
+    };
+    let reservations: Vec<_> = all_facts
+        .loan_reserved_at
+        .iter()
+        .filter(|&&(b, _)| b == loan)
+        .map(|&(_, p)| fact_name("loan_reserved_at", p))
+        .collect();
+    let activations: Vec<_> = all_facts
+        .loan_activated_at
+        .iter()
+        .filter(|&&(b, _)| b == loan)
+        .map(|&(_, p)| fact_name("loan_activated_at", p))
+        .collect();
+
+    let mut conflicts = vec![];
+    let mut push = |conflict: String, read: bool| {
+        let two_phase = if read { &activations } else { &reservations };
+        if two_phase.is_empty() {
+            conflicts.push(conflict);
+        } else {
+            for fact in two_phase {
+                conflicts.push(format!("{} with {}", conflict, fact));
+            }
+        }
+    };
+
+    if all_facts.invalidates_by_read.contains(&(point, loan)) {
+        push(format!("invalidates_by_read({}, {})", point_name, loan_name), true);
+    } else if all_facts.invalidates.contains(&(point, loan)) {
+        push(format!("invalidates({}, {})", point_name, loan_name), false);
+    }
+    for (access, kind) in output.error_kinds_at(point, loan) {
+        push(
+            format!(
+                "invalidates_by_access({}, {}, {}, {})",
+                point_name,
+                loan_name,
+                access.name(),
+                kind.name()
+            ),
+            access == AccessKind::Read,
+        );
+    }
+    conflicts
+}
@@ -1,6 +1,7 @@
 use polonius_engine;
+use std::fmt;
 
-crate type AllFacts = polonius_engine::AllFacts<Region, Loan, Point>;
+crate type AllFacts = polonius_engine::AllFacts<Region, Loan, Point, Variable, MovePath>;
 
 macro_rules! index_type {
     ($t:ident) => {
@@ -34,3 +35,61 @@ macro_rules! index_type {
 index_type!(Region);
 index_type!(Loan);
 index_type!(Point);
+index_type!(Variable);
+index_type!(MovePath);
+
+/// Whether a point is at the start of a MIR statement, or in the
+/// middle of it (i.e., where the statement takes effect).
+#[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Copy, Debug, Hash)]
+crate enum PointKind {
+    Start,
+    Mid,
+}
+
+/// The MIR location named by a point, like `Mid(bb3[2])`. Locations
+/// are ordered by basic block, then statement, then kind.
+#[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Copy, Debug, Hash)]
+crate struct Location {
+    crate block: usize,
+    crate statement: usize,
+    crate kind: PointKind,
+}
+
+impl Location {
+    /// Parses a point name as emitted by rustc, with or without the
+    /// surrounding quotes. Returns `None` if it isn't of the form
+    /// `Start(bbB[S])` or `Mid(bbB[S])`.
+    crate fn parse(name: &str) -> Option<Location> {
+        let name = name.trim_matches('"');
+        let (kind, rest) = if name.starts_with("Start(") {
+            (PointKind::Start, &name["Start(".len()..])
+        } else if name.starts_with("Mid(") {
+            (PointKind::Mid, &name["Mid(".len()..])
+        } else {
+            return None;
+        };
+
+        if !rest.starts_with("bb") || !rest.ends_with("])") {
+            return None;
+        }
+
+        let mut parts = rest["bb".len()..rest.len() - "])".len()].splitn(2, '[');
+        let block = parts.next()?.parse().ok()?;
+        let statement = parts.next()?.parse().ok()?;
+        Some(Location {
+            block,
+            statement,
+            kind,
+        })
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            PointKind::Start => "Start",
+            PointKind::Mid => "Mid",
+        };
+        write!(f, "{}(bb{}[{}])", kind, self.block, self.statement)
+    }
+}
@@ -0,0 +1,140 @@
+//! Renders the CFG of a function as a graphviz digraph, labeling each
+//! point with the loans live there. Points of the same basic block
+//! are clustered together, and points with errors are highlighted.
+
+use crate::facts::{AllFacts, Loan, MovePath, Point, Region};
+use crate::intern::InternerTables;
+use polonius_engine::Output;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+
+crate fn dump_graphviz(
+    name: &str,
+    all_facts: &AllFacts,
+    output: &Output<Region, Loan, Point, MovePath>,
+    tables: &InternerTables,
+    stream: &mut dyn Write,
+) -> io::Result<()> {
+    let points: BTreeSet<Point> = all_facts
+        .cfg_edge
+        .iter()
+        .flat_map(|&(p, q)| vec![p, q])
+        .collect();
+
+    // group the points by basic block; those whose names can't be
+    // parsed go in the `None` group, outside of any cluster.
+    let mut blocks: BTreeMap<Option<usize>, Vec<Point>> = BTreeMap::new();
+    for &point in &points {
+        let block = tables.point_location(point).map(|location| location.block);
+        blocks.entry(block).or_insert(vec![]).push(point);
+    }
+
+    writeln!(stream, "digraph \"{}\" {{", escape(name))?;
+    writeln!(stream, "    node [shape=box, fontname=monospace];")?;
+
+    for (block, block_points) in &mut blocks {
+        block_points.sort_by_key(|&point| (tables.point_location(point), point));
+
+        let indent = match block {
+            Some(block) => {
+                writeln!(stream, "    subgraph cluster_bb{} {{", block)?;
+                writeln!(stream, "        label=\"bb{}\";", block)?;
+                "        "
+            }
+            None => "    ",
+        };
+
+        for &point in block_points.iter() {
+            let (label, has_errors) = node_label(point, output, tables);
+            let style = if has_errors { ", color=red, penwidth=2" } else { "" };
+            writeln!(
+                stream,
+                "{}{} [label=\"{}\"{}];",
+                indent,
+                node_id(point),
+                label,
+                style
+            )?;
+        }
+
+        if block.is_some() {
+            writeln!(stream, "    }}")?;
+        }
+    }
+
+    for &(p, q) in &all_facts.cfg_edge {
+        writeln!(stream, "    {} -> {};", node_id(p), node_id(q))?;
+    }
+
+    writeln!(stream, "}}")?;
+    Ok(())
+}
+
+fn node_id(point: Point) -> String {
+    let index: usize = point.into();
+    format!("p{}", index)
+}
+
+/// The label of a point: its name, the loans live there, and (if
+/// `output` has the debugging relations) the live regions and subset
+/// relation. Also returns whether there are errors at the point.
+fn node_label(
+    point: Point,
+    output: &Output<Region, Loan, Point, MovePath>,
+    tables: &InternerTables,
+) -> (String, bool) {
+    let region_name = |&region: &Region| unquote(tables.regions.untern(region));
+    let loan_name = |&loan: &Loan| unquote(tables.loans.untern(loan));
+
+    let mut lines = vec![unquote(tables.points.untern(point))];
+
+    let loans = output.borrows_in_scope_at(point);
+    if !loans.is_empty() {
+        let loans: Vec<_> = loans.iter().map(loan_name).collect();
+        lines.push(format!("borrow_live_at: {}", loans.join(", ")));
+    }
+
+    if output.dump_enabled {
+        let regions = output.regions_live_at(point);
+        if !regions.is_empty() {
+            let regions: Vec<_> = regions.iter().map(region_name).collect();
+            lines.push(format!("region_live_at: {}", regions.join(", ")));
+        }
+
+        let subsets = output.subsets_at(point);
+        for (r1, supersets) in subsets.iter() {
+            let supersets: Vec<_> = supersets.iter().map(region_name).collect();
+            lines.push(format!(
+                "subset: {} <= {}",
+                region_name(r1),
+                supersets.join(", ")
+            ));
+        }
+    }
+
+    let errors = output.potential_errors.get(&point);
+    if let Some(errors) = errors {
+        let errors: Vec<_> = errors.iter().map(loan_name).collect();
+        lines.push(format!("potential_errors: {}", errors.join(", ")));
+    }
+
+    let move_errors = output.move_errors_at(point);
+    if !move_errors.is_empty() {
+        let paths: Vec<_> = move_errors
+            .iter()
+            .map(|&path| unquote(tables.paths.untern(path)))
+            .collect();
+        lines.push(format!("move_errors: {}", paths.join(", ")));
+    }
+
+    let label: String = lines.iter().map(|line| escape(line) + "\\l").collect();
+    (label, errors.is_some() || !move_errors.is_empty())
+}
+
+fn unquote(name: &str) -> String {
+    name.trim_matches('"').to_string()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
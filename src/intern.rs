@@ -6,6 +6,9 @@ use std::collections::HashMap;
 crate struct Interner<TargetType: From<usize> + Copy> {
     strings: HashMap<String, TargetType>,
     rev_strings: Vec<String>,
+    /// The MIR location named by each string, parsed once when it is
+    /// interned; only kept for the points.
+    locations: Option<Vec<Option<Location>>>,
 }
 
 impl<TargetType> Interner<TargetType>
@@ -16,14 +19,32 @@ where
         Self {
             strings: HashMap::new(),
             rev_strings: vec![],
+            locations: None,
         }
     }
 
+    fn with_locations() -> Self {
+        Self {
+            locations: Some(vec![]),
+            ..Self::new()
+        }
+    }
+
+    crate fn len(&self) -> usize {
+        self.rev_strings.len()
+    }
+
     crate fn untern(&self, data: TargetType) -> &str {
         let data: usize = data.into();
         &self.rev_strings[data]
     }
 
+    /// The atom interned for `data`, if any; unlike `intern`, this
+    /// doesn't create one.
+    crate fn lookup(&self, data: &str) -> Option<TargetType> {
+        self.strings.get(data).cloned()
+    }
+
     crate fn intern(&mut self, data: &str) -> TargetType {
         if let Some(&interned) = self.strings.get(data) {
             return interned;
@@ -31,6 +52,9 @@ where
 
         let index = TargetType::from(self.strings.len());
         self.rev_strings.push(data.to_string());
+        if let Some(ref mut locations) = self.locations {
+            locations.push(Location::parse(data));
+        }
         *self.strings.entry(data.to_string()).or_insert(index)
     }
 }
@@ -39,6 +63,8 @@ crate struct InternerTables {
     crate regions: Interner<Region>,
     crate loans: Interner<Loan>,
     crate points: Interner<Point>,
+    crate variables: Interner<Variable>,
+    crate paths: Interner<MovePath>,
 }
 
 impl InternerTables {
@@ -46,9 +72,17 @@ impl InternerTables {
         Self {
             regions: Interner::new(),
             loans: Interner::new(),
-            points: Interner::new(),
+            points: Interner::with_locations(),
+            variables: Interner::new(),
+            paths: Interner::new(),
         }
     }
+
+    /// The MIR location named by `point`, if its name can be parsed.
+    crate fn point_location(&self, point: Point) -> Option<Location> {
+        let index: usize = point.into();
+        self.points.locations.as_ref().and_then(|locations| locations[index])
+    }
 }
 
 crate trait InternTo<To> {
@@ -68,6 +102,8 @@ macro_rules! intern_impl {
 intern_impl!(Region, regions);
 intern_impl!(Loan, loans);
 intern_impl!(Point, points);
+intern_impl!(Variable, variables);
+intern_impl!(MovePath, paths);
 
 impl<A, FromA, B, FromB> InternTo<(A, B)> for (FromA, FromB)
 where
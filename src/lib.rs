@@ -12,15 +12,24 @@ extern crate fxhash;
 extern crate histo;
 extern crate structopt;
 extern crate polonius_engine;
-
 #[macro_use]
+extern crate serde_json;
 extern crate clap;
 
+mod bench;
+mod binary;
+mod builder;
+mod compare;
 mod dump;
+mod explain;
 mod facts;
+mod graphviz;
 mod intern;
 mod output;
+mod reduce;
+mod stats;
 mod tab_delim;
 mod test;
+mod validate;
 
 pub mod cli;
@@ -8,9 +8,16 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+//! Statistics on the shape of the `subset` relation: the in- and
+//! out-degree of each region at each point, which is what makes some
+//! inputs blow up in the transitive rules.
+
 use crate::facts::{Point, Region};
 use fxhash::FxHashMap;
 use histo::Histogram;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
 
 #[derive(Clone, Debug)]
 crate struct RegionDegrees {
@@ -26,6 +33,20 @@ impl RegionDegrees {
         }
     }
 
+    /// Computes the degrees of a `subset` relation, as found in an
+    /// `Output` computed with the debugging relations.
+    crate fn from_subset(subset: &FxHashMap<Point, BTreeMap<Region, BTreeSet<Region>>>) -> Self {
+        let mut degrees = Self::new();
+        for (&p, map) in subset {
+            for (&r1, supersets) in map {
+                for &r2 in supersets {
+                    degrees.update_degrees(r1, r2, p);
+                }
+            }
+        }
+        degrees
+    }
+
     crate fn update_degrees(&mut self, r1: Region, r2: Region, p: Point) {
         *self.in_degree.entry((r2, p)).or_insert(0) += 1;
         *self.out_degree.entry((r1, p)).or_insert(0) += 1;
@@ -57,9 +78,49 @@ impl RegionDegrees {
         for v in self.in_degree.values() {
             histo_in.add(*v as u64);
         }
-        for v in self.in_degree.values() {
+        for v in self.out_degree.values() {
             histo_out.add(*v as u64);
         }
         (histo_in, histo_out)
     }
+
+    crate fn dump(&self, stream: &mut dyn Write) -> io::Result<()> {
+        let (histo_in, histo_out) = self.histogram();
+        writeln!(stream, "Subset relation statistics:")?;
+        writeln!(stream, "    max in-degree: {}", self.max_in_degree())?;
+        writeln!(stream, "    max out-degree: {}", self.max_out_degree())?;
+        writeln!(
+            stream,
+            "    regions with both in- and out-degree over 1: {}",
+            if self.has_multidegree() { "yes" } else { "no" }
+        )?;
+        writeln!(stream, "In-degree histogram:\n{}", histo_in)?;
+        writeln!(stream, "Out-degree histogram:\n{}", histo_out)?;
+        Ok(())
+    }
+
+    crate fn to_json(&self) -> Value {
+        let (histo_in, histo_out) = self.histogram();
+        json!({
+            "max_in_degree": self.max_in_degree(),
+            "max_out_degree": self.max_out_degree(),
+            "has_multidegree": self.has_multidegree(),
+            "in_degree_histogram": histogram_to_json(&histo_in),
+            "out_degree_histogram": histogram_to_json(&histo_out),
+        })
+    }
+}
+
+fn histogram_to_json(histogram: &Histogram) -> Value {
+    let buckets: Vec<_> = histogram
+        .buckets()
+        .map(|bucket| {
+            json!({
+                "start": bucket.start(),
+                "end": bucket.end(),
+                "count": bucket.count(),
+            })
+        })
+        .collect();
+    json!(buckets)
 }
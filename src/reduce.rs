@@ -0,0 +1,268 @@
+//! Minimizes an input while some behavior persists: either two
+//! algorithms disagreeing on it, or an algorithm reporting a given
+//! error. This is a simple form of delta debugging: whole basic blocks
+//! are removed first, then chunks of each relation of decreasing
+//! sizes, until no single tuple can be removed anymore.
+
+use crate::cli::load_facts;
+use crate::compare;
+use crate::facts::{AllFacts, Loan, Point};
+use crate::intern::{Interner, InternerTables};
+use crate::tab_delim;
+use failure::{self, Error};
+use fxhash::FxHashSet;
+use polonius_engine::{Algorithm, Output};
+use std::cmp;
+use std::collections::BTreeMap;
+use std::path::Path;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+crate struct ReduceOpt {
+    /// The two algorithms whose disagreement is preserved, or the one
+    /// algorithm whose error is preserved with `--error`
+    #[structopt(
+        short = "a",
+        default_value = "Naive,DatafrogOpt",
+        raw(
+            use_delimiter = "true",
+            possible_values = "&Algorithm::variants()",
+            case_insensitive = "true"
+        )
+    )]
+    algorithms: Vec<Algorithm>,
+    /// Preserve this potential error instead of a disagreement
+    #[structopt(
+        long = "error",
+        raw(number_of_values = "2", value_names = r#"&["POINT", "LOAN"]"#)
+    )]
+    error: Vec<String>,
+    input_dir: String,
+    output_dir: String,
+}
+
+/// The behavior to preserve while reducing.
+#[derive(Copy, Clone, Debug)]
+crate enum Goal {
+    /// The two algorithms disagree, see `compare::compare_outputs`.
+    Disagreement(Algorithm, Algorithm),
+
+    /// The algorithm reports the potential error `(point, loan)`.
+    Error {
+        algorithm: Algorithm,
+        point: Point,
+        loan: Loan,
+    },
+}
+
+impl Goal {
+    crate fn holds(&self, all_facts: &AllFacts) -> bool {
+        match *self {
+            Goal::Disagreement(algorithm1, algorithm2) => {
+                let output1 = Output::compute(all_facts, algorithm1, false);
+                let output2 = Output::compute(all_facts, algorithm2, false);
+                !compare::compare_outputs((algorithm1, &output1), (algorithm2, &output2), None)
+            }
+            Goal::Error {
+                algorithm,
+                point,
+                loan,
+            } => Output::compute(all_facts, algorithm, false)
+                .potential_errors
+                .get(&point)
+                .map_or(false, |loans| loans.contains(&loan)),
+        }
+    }
+}
+
+crate fn reduce_main(opt: &ReduceOpt) -> Result<(), Error> {
+    let tables = &mut InternerTables::new();
+    let all_facts = load_facts(tables, Path::new(&opt.input_dir), false)?;
+
+    let goal = match (&opt.algorithms[..], &opt.error[..]) {
+        (&[algorithm1, algorithm2], &[]) => Goal::Disagreement(algorithm1, algorithm2),
+        (&[algorithm], &[ref point, ref loan]) => Goal::Error {
+            algorithm,
+            point: lookup(&tables.points, point)?,
+            loan: lookup(&tables.loans, loan)?,
+        },
+        (_, &[]) => Err(failure::err_msg("expected two algorithms to compare"))?,
+        (_, _) => Err(failure::err_msg("`--error` needs a single algorithm, e.g. `-a Naive`"))?,
+    };
+
+    if !goal.holds(&all_facts) {
+        Err(failure::err_msg(format!(
+            "{:?} does not hold on the input, there is nothing to reduce",
+            goal
+        )))?;
+    }
+
+    let before = tuple_count(&all_facts);
+    let reduced = reduce(all_facts, tables, |all_facts| goal.holds(all_facts));
+    eprintln!("reduced from {} to {} tuples", before, tuple_count(&reduced));
+
+    tab_delim::write_tab_delimited_facts(tables, &reduced, Path::new(&opt.output_dir))?;
+    Ok(())
+}
+
+/// Looks up an atom by name, with or without the quotes rustc puts
+/// around names.
+fn lookup<T>(interner: &Interner<T>, name: &str) -> Result<T, Error>
+where
+    T: From<usize> + Into<usize> + Copy,
+{
+    interner
+        .lookup(name)
+        .or_else(|| interner.lookup(&format!("\"{}\"", name)))
+        .ok_or_else(|| failure::err_msg(format!("`{}` does not appear in the input", name)))
+}
+
+/// Removes as many tuples from `all_facts` as possible while
+/// `interesting` holds, which it must initially.
+crate fn reduce(
+    mut all_facts: AllFacts,
+    tables: &InternerTables,
+    mut interesting: impl FnMut(&AllFacts) -> bool,
+) -> AllFacts {
+    loop {
+        let before = tuple_count(&all_facts);
+        remove_blocks(&mut all_facts, tables, &mut interesting);
+
+        macro_rules! remove_tuples {
+            ($($t:ident,)*) => {
+                $(
+                    remove_chunks(&mut all_facts, |facts| &mut facts.$t, &mut interesting);
+                )*
+            }
+        }
+
+        remove_tuples! {
+            cfg_edge,
+            borrow_region,
+            universal_region,
+            killed,
+            outlives,
+            region_live_at,
+            invalidates,
+            var_defined,
+            var_used,
+            var_drop_used,
+            var_uses_region,
+            var_drops_region,
+            child,
+            path_assigned_at,
+            path_moved_at,
+            path_accessed_at,
+            loan_path,
+            loan_reserved_at,
+            loan_activated_at,
+            invalidates_by_read,
+            invalidates_by_access,
+        }
+
+        let after = tuple_count(&all_facts);
+        eprintln!("reduced to {} tuples", after);
+        if after == before {
+            return all_facts;
+        }
+    }
+}
+
+/// Tries to remove each basic block, i.e. all the tuples mentioning
+/// one of its points.
+fn remove_blocks(
+    all_facts: &mut AllFacts,
+    tables: &InternerTables,
+    interesting: &mut impl FnMut(&AllFacts) -> bool,
+) {
+    let mut blocks: BTreeMap<usize, FxHashSet<Point>> = BTreeMap::new();
+    for point in (0..tables.points.len()).map(Point::from) {
+        if let Some(location) = tables.point_location(point) {
+            blocks.entry(location.block).or_insert_with(FxHashSet::default).insert(point);
+        }
+    }
+
+    for points in blocks.values() {
+        let candidate = without_points(all_facts, points);
+        if tuple_count(&candidate) < tuple_count(all_facts) && interesting(&candidate) {
+            *all_facts = candidate;
+        }
+    }
+}
+
+fn without_points(all_facts: &AllFacts, points: &FxHashSet<Point>) -> AllFacts {
+    let mut all_facts = all_facts.clone();
+    let keep = |point| !points.contains(&point);
+    all_facts.borrow_region.retain(|&(_, _, p)| keep(p));
+    all_facts.cfg_edge.retain(|&(p, q)| keep(p) && keep(q));
+    all_facts.killed.retain(|&(_, p)| keep(p));
+    all_facts.outlives.retain(|&(_, _, p)| keep(p));
+    all_facts.region_live_at.retain(|&(_, p)| keep(p));
+    all_facts.invalidates.retain(|&(p, _)| keep(p));
+    all_facts.var_defined.retain(|&(_, p)| keep(p));
+    all_facts.var_used.retain(|&(_, p)| keep(p));
+    all_facts.var_drop_used.retain(|&(_, p)| keep(p));
+    all_facts.path_assigned_at.retain(|&(_, p)| keep(p));
+    all_facts.path_moved_at.retain(|&(_, p)| keep(p));
+    all_facts.path_accessed_at.retain(|&(_, p)| keep(p));
+    all_facts.loan_reserved_at.retain(|&(_, p)| keep(p));
+    all_facts.loan_activated_at.retain(|&(_, p)| keep(p));
+    all_facts.invalidates_by_read.retain(|&(p, _)| keep(p));
+    all_facts.invalidates_by_access.retain(|&(p, _, _, _)| keep(p));
+    all_facts
+}
+
+/// Tries to remove chunks of the relation selected by `relation`,
+/// halving the chunk size after each pass down to single tuples.
+fn remove_chunks<T>(
+    all_facts: &mut AllFacts,
+    relation: impl Fn(&mut AllFacts) -> &mut Vec<T>,
+    interesting: &mut impl FnMut(&AllFacts) -> bool,
+) {
+    let mut chunk_size = cmp::max(relation(all_facts).len() / 2, 1);
+    loop {
+        let mut start = 0;
+        while start < relation(all_facts).len() {
+            let mut candidate = all_facts.clone();
+            {
+                let rows = relation(&mut candidate);
+                let end = cmp::min(start + chunk_size, rows.len());
+                rows.drain(start..end);
+            }
+            if interesting(&candidate) {
+                *all_facts = candidate;
+            } else {
+                start += chunk_size;
+            }
+        }
+
+        if chunk_size == 1 {
+            return;
+        }
+        chunk_size /= 2;
+    }
+}
+
+crate fn tuple_count(all_facts: &AllFacts) -> usize {
+    all_facts.borrow_region.len()
+        + all_facts.universal_region.len()
+        + all_facts.cfg_edge.len()
+        + all_facts.killed.len()
+        + all_facts.outlives.len()
+        + all_facts.region_live_at.len()
+        + all_facts.invalidates.len()
+        + all_facts.var_defined.len()
+        + all_facts.var_used.len()
+        + all_facts.var_drop_used.len()
+        + all_facts.var_uses_region.len()
+        + all_facts.var_drops_region.len()
+        + all_facts.child.len()
+        + all_facts.path_assigned_at.len()
+        + all_facts.path_moved_at.len()
+        + all_facts.path_accessed_at.len()
+        + all_facts.loan_path.len()
+        + all_facts.loan_reserved_at.len()
+        + all_facts.loan_activated_at.len()
+        + all_facts.invalidates_by_read.len()
+        + all_facts.invalidates_by_access.len()
+}
@@ -0,0 +1,227 @@
+//! Describes the shape of the inputs in fact directories, without
+//! running any analysis: how many atoms and tuples they contain, and
+//! what their CFG looks like.
+
+use crate::cli::load_facts;
+use crate::dump::{self, OutputFormat};
+use crate::facts::{AllFacts, Point};
+use crate::intern::InternerTables;
+use failure::Error;
+use fxhash::{FxHashMap, FxHashSet};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::Path;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+crate struct StatsOpt {
+    #[structopt(
+        long = "format",
+        default_value = "text",
+        raw(possible_values = "&OutputFormat::variants()", case_insensitive = "true")
+    )]
+    format: OutputFormat,
+    #[structopt(raw(required = "true"))]
+    fact_dirs: Vec<String>,
+}
+
+crate fn stats(opt: &StatsOpt) -> Result<(), Error> {
+    let mut json_results = vec![];
+    for facts_dir in &opt.fact_dirs {
+        let tables = &mut InternerTables::new();
+        let all_facts = match load_facts(tables, Path::new(facts_dir), false) {
+            Ok(all_facts) => all_facts,
+            Err(error) => {
+                eprintln!("`{}`: {}", facts_dir, error);
+                continue;
+            }
+        };
+        let stats = input_stats(&all_facts, tables);
+
+        match opt.format {
+            OutputFormat::Text => {
+                println!("--------------------------------------------------");
+                println!("Directory: {}", facts_dir);
+                print_stats(&stats);
+            }
+
+            OutputFormat::Json => {
+                let mut result = stats;
+                result["directory"] = json!(facts_dir);
+                json_results.push(result);
+            }
+        }
+    }
+
+    if opt.format == OutputFormat::Json {
+        dump::dump_json(&json!(json_results), &None)?;
+    }
+
+    Ok(())
+}
+
+/// Computes the metrics of an input, as a JSON object.
+crate fn input_stats(all_facts: &AllFacts, tables: &InternerTables) -> Value {
+    let points: BTreeSet<Point> = (0..tables.points.len()).map(Point::from).collect();
+    let blocks: FxHashSet<usize> = points
+        .iter()
+        .filter_map(|&point| tables.point_location(point))
+        .map(|location| location.block)
+        .collect();
+    let universal_regions: FxHashSet<_> = all_facts.universal_region.iter().collect();
+
+    let mut successors: FxHashMap<Point, Vec<Point>> = FxHashMap::default();
+    for &(p, q) in &all_facts.cfg_edge {
+        successors.entry(p).or_insert(vec![]).push(q);
+    }
+
+    let mut stats = json!({
+        "points": points.len(),
+        "basic_blocks": blocks.len(),
+        "regions": tables.regions.len(),
+        "universal_regions": universal_regions.len(),
+        "loans": tables.loans.len(),
+        "variables": tables.variables.len(),
+        "move_paths": tables.paths.len(),
+        "region_live_at_per_point": ratio(all_facts.region_live_at.len(), points.len()),
+        "cfg_branching_factor": ratio(all_facts.cfg_edge.len(), successors.len()),
+        "loops": count_back_edges(&points, &successors),
+        "relations": {},
+    });
+
+    macro_rules! relation_sizes {
+        ($($t:ident,)*) => {
+            $(
+                stats["relations"][stringify!($t)] = json!(all_facts.$t.len());
+            )*
+        }
+    }
+
+    relation_sizes! {
+        borrow_region,
+        universal_region,
+        cfg_edge,
+        killed,
+        outlives,
+        region_live_at,
+        invalidates,
+        var_defined,
+        var_used,
+        var_drop_used,
+        var_uses_region,
+        var_drops_region,
+        child,
+        path_assigned_at,
+        path_moved_at,
+        path_accessed_at,
+        loan_path,
+        loan_reserved_at,
+        loan_activated_at,
+        invalidates_by_read,
+        invalidates_by_access,
+    }
+
+    stats
+}
+
+fn print_stats(stats: &Value) {
+    for key in &[
+        "points",
+        "basic_blocks",
+        "regions",
+        "universal_regions",
+        "loans",
+        "variables",
+        "move_paths",
+        "region_live_at_per_point",
+        "cfg_branching_factor",
+        "loops",
+    ] {
+        print_value(key, &stats[key]);
+    }
+
+    println!("Relation sizes:");
+    if let Some(relations) = stats["relations"].as_object() {
+        for (name, size) in relations {
+            print_value(&format!("  {}", name), size);
+        }
+    }
+}
+
+fn print_value(name: &str, value: &Value) {
+    match value.as_f64() {
+        Some(value) if !value.is_nan() && value.fract() != 0.0 => {
+            println!("{:<26} {:0.2}", format!("{}:", name), value)
+        }
+        _ => println!("{:<26} {}", format!("{}:", name), value),
+    }
+}
+
+/// `numerator / denominator`, or 0 if there is nothing to divide by.
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+/// Counts the loops of the CFG, as the number of back edges found by a
+/// depth-first search starting from the points without predecessors
+/// (and then from any point not reached yet, in case some loops are
+/// not reachable from those).
+fn count_back_edges(
+    points: &BTreeSet<Point>,
+    successors: &FxHashMap<Point, Vec<Point>>,
+) -> usize {
+    let has_predecessor: FxHashSet<Point> =
+        successors.values().flat_map(|v| v.iter().cloned()).collect();
+    let roots = points
+        .iter()
+        .filter(|point| !has_predecessor.contains(point))
+        .chain(points.iter());
+
+    #[derive(Copy, Clone, PartialEq, Eq)]
+    enum State {
+        OnStack,
+        Done,
+    }
+
+    let mut states: FxHashMap<Point, State> = FxHashMap::default();
+    let mut back_edges = 0;
+    for &root in roots {
+        if states.contains_key(&root) {
+            continue;
+        }
+
+        // each frame is a point and the index of its next successor to visit
+        let mut stack = vec![(root, 0)];
+        states.insert(root, State::OnStack);
+        loop {
+            let (point, next) = match stack.last() {
+                Some(&frame) => frame,
+                None => break,
+            };
+            let point_successors = successors.get(&point).map(|v| &v[..]).unwrap_or(&[]);
+            match point_successors.get(next) {
+                Some(&successor) => {
+                    stack.last_mut().unwrap().1 += 1;
+                    match states.get(&successor).cloned() {
+                        Some(State::OnStack) => back_edges += 1,
+                        Some(State::Done) => {}
+                        None => {
+                            states.insert(successor, State::OnStack);
+                            stack.push((successor, 0));
+                        }
+                    }
+                }
+                None => {
+                    states.insert(point, State::Done);
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    back_edges
+}
@@ -1,37 +1,86 @@
-use crate::facts::AllFacts;
+use crate::facts::{AllFacts, Loan, MovePath, Point, Region, Variable};
 use crate::intern::{InternTo, InternerTables};
-use std::fs::File;
-use std::io::{self, prelude::*};
-use std::path::Path;
-use std::process;
+use polonius_engine::{AccessKind, LoanKind};
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, prelude::*, BufWriter};
+use std::path::{Path, PathBuf};
 
 trait FromTabDelimited<'input>: Sized {
+    /// The number of columns making up a row.
+    const COLUMNS: usize;
+
     fn parse(
         tables: &mut InternerTables,
         inputs: &mut dyn Iterator<Item = &'input str>,
     ) -> Option<Self>;
 }
 
+/// The fact files without which the analysis is meaningless; see
+/// `check_core_facts`.
+crate const CORE_FACTS: &[&str] = &["cfg_edge", "region_live_at"];
+
+/// The fact files only needed by some analyses, e.g. the variable
+/// facts from which `region_live_at` can be computed, or the move path
+/// facts of the initialization analysis: they are treated as empty when
+/// missing, without a warning.
+crate const OPTIONAL_FACTS: &[&str] = &[
+    "var_defined",
+    "var_used",
+    "var_drop_used",
+    "var_uses_region",
+    "var_drops_region",
+    "child",
+    "path_assigned_at",
+    "path_moved_at",
+    "path_accessed_at",
+    "loan_path",
+    "loan_reserved_at",
+    "loan_activated_at",
+    "invalidates_by_read",
+    "invalidates_by_access",
+];
+
+/// Loads the facts from the `.facts` files in `facts_dir`. Different
+/// rustc versions emit different sets of files, so missing files are
+/// treated as empty relations (with a warning, unless they are
+/// `OPTIONAL_FACTS`).
 crate fn load_tab_delimited_facts(
     tables: &mut InternerTables,
     facts_dir: &Path,
 ) -> io::Result<AllFacts> {
+    if !facts_dir.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("`{}` is not a directory", facts_dir.display()),
+        ));
+    }
+
     macro_rules! load_facts {
-        (from ($tables:expr, $facts_dir:expr) load AllFacts { $($t:ident,)* }) => {
-            Ok(AllFacts {
+        (from ($tables:expr, $facts_dir:expr, $missing:expr) load AllFacts { $($t:ident,)* }) => {
+            AllFacts {
                 $(
                     $t: {
                         let filename = format!("{}.facts", stringify!($t));
                         let facts_file = $facts_dir.join(&filename);
-                        load_tab_delimited_file($tables, &facts_file)?
+                        if facts_file.exists() {
+                            load_tab_delimited_file($tables, &facts_file)?
+                        } else {
+                            if !OPTIONAL_FACTS.contains(&stringify!($t)) {
+                                $missing.push(filename);
+                            }
+                            Vec::new()
+                        }
                     },
                 )*
-            })
+            }
         }
     }
 
-    load_facts! {
-        from (tables, facts_dir) load AllFacts {
+    let mut missing_files = vec![];
+    let all_facts = load_facts! {
+        from (tables, facts_dir, missing_files) load AllFacts {
             borrow_region,
             universal_region,
             cfg_edge,
@@ -39,10 +88,151 @@ crate fn load_tab_delimited_facts(
             outlives,
             region_live_at,
             invalidates,
+            var_defined,
+            var_used,
+            var_drop_used,
+            var_uses_region,
+            var_drops_region,
+            child,
+            path_assigned_at,
+            path_moved_at,
+            path_accessed_at,
+            loan_path,
+            loan_reserved_at,
+            loan_activated_at,
+            invalidates_by_read,
+            invalidates_by_access,
+        }
+    };
+
+    if !missing_files.is_empty() {
+        eprintln!(
+            "warning: `{}`: missing fact files treated as empty: {}",
+            facts_dir.display(),
+            missing_files.join(", ")
+        );
+    }
+
+    Ok(all_facts)
+}
+
+/// Writes `all_facts` as `.facts` files in `facts_dir`, creating the
+/// directory if needed, using the names in `tables`; this is the
+/// inverse of `load_tab_delimited_facts`.
+crate fn write_tab_delimited_facts(
+    tables: &InternerTables,
+    all_facts: &AllFacts,
+    facts_dir: &Path,
+) -> io::Result<()> {
+    fs::create_dir_all(facts_dir)?;
+
+    macro_rules! write_facts {
+        ($($t:ident,)*) => {
+            $(
+                let filename = format!("{}.facts", stringify!($t));
+                write_tab_delimited_file(tables, &all_facts.$t, &facts_dir.join(&filename))?;
+            )*
+        }
+    }
+
+    write_facts! {
+        borrow_region,
+        universal_region,
+        cfg_edge,
+        killed,
+        outlives,
+        region_live_at,
+        invalidates,
+        var_defined,
+        var_used,
+        var_drop_used,
+        var_uses_region,
+        var_drops_region,
+        child,
+        path_assigned_at,
+        path_moved_at,
+        path_accessed_at,
+        loan_path,
+        loan_reserved_at,
+        loan_activated_at,
+        invalidates_by_read,
+        invalidates_by_access,
+    }
+
+    Ok(())
+}
+
+/// Checks that all the `CORE_FACTS` files are present in `facts_dir`.
+crate fn check_core_facts(facts_dir: &Path) -> io::Result<()> {
+    let missing_files: Vec<_> = CORE_FACTS
+        .iter()
+        .map(|name| format!("{}.facts", name))
+        .filter(|filename| !facts_dir.join(filename).exists())
+        .collect();
+
+    if missing_files.is_empty() {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("missing required fact files: {}", missing_files.join(", ")),
+        ))
+    }
+}
+
+/// A malformed line of a `.facts` file. It is returned as the payload
+/// of an `io::Error` of kind `InvalidData`.
+#[derive(Debug)]
+crate struct ParseError {
+    crate path: PathBuf,
+    /// 1-based line number
+    crate line: usize,
+    /// 1-based index of the first missing, extra or invalid column
+    crate column: usize,
+    /// the offending line, the extra data at the end of it, or the
+    /// invalid value
+    crate text: String,
+    crate kind: ParseErrorKind,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+crate enum ParseErrorKind {
+    /// The line doesn't have the number of columns its relation expects.
+    WrongColumnCount { expected: usize, found: usize },
+
+    /// The value isn't one of those its column allows, like an unknown
+    /// access kind.
+    InvalidValue,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            ParseErrorKind::WrongColumnCount { expected, found } => write!(
+                f,
+                "{} on line {}, column {} of `{}`: expected {} columns, found {}: `{}`",
+                if found < expected { "missing data" } else { "extra data" },
+                self.line,
+                self.column,
+                self.path.display(),
+                expected,
+                found,
+                self.text,
+            ),
+            ParseErrorKind::InvalidValue => write!(
+                f,
+                "invalid value on line {}, column {} of `{}`: `{}`",
+                self.line,
+                self.column,
+                self.path.display(),
+                self.text,
+            ),
         }
     }
 }
 
+impl Error for ParseError {}
+
 fn load_tab_delimited_file<Row>(tables: &mut InternerTables, path: &Path) -> io::Result<Vec<Row>>
 where
     Row: for<'input> FromTabDelimited<'input>,
@@ -51,30 +241,151 @@ where
     let mut result = Vec::new();
     for (index, line) in io::BufReader::new(file).lines().enumerate() {
         let line = line?;
-        let mut columns = line.split("\t");
-        let row = match FromTabDelimited::parse(tables, &mut columns) {
+        let columns: Vec<&str> = line.split("\t").collect();
+        let expected_columns = <Row as FromTabDelimited>::COLUMNS;
+        if columns.len() != expected_columns {
+            let (column, text) = if columns.len() < expected_columns {
+                (columns.len() + 1, line.clone())
+            } else {
+                (expected_columns + 1, columns[expected_columns..].join("\t"))
+            };
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                ParseError {
+                    path: path.to_owned(),
+                    line: index + 1,
+                    column,
+                    text,
+                    kind: ParseErrorKind::WrongColumnCount {
+                        expected: expected_columns,
+                        found: columns.len(),
+                    },
+                },
+            ));
+        }
+
+        // The number of columns is right, so only a value which isn't an
+        // atom, like an unknown access kind, can fail to parse: it is the
+        // last one consumed.
+        let mut inputs = columns.iter().cloned();
+        match FromTabDelimited::parse(tables, &mut inputs) {
+            Some(row) => result.push(row),
             None => {
-                eprintln!("error parsing line {} of `{}`", index + 1, path.display());
-                process::exit(1);
+                let column = expected_columns - inputs.len();
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    ParseError {
+                        path: path.to_owned(),
+                        line: index + 1,
+                        column,
+                        text: columns[column - 1].to_string(),
+                        kind: ParseErrorKind::InvalidValue,
+                    },
+                ));
             }
+        }
+    }
+    Ok(result)
+}
 
-            Some(v) => v,
-        };
+fn write_tab_delimited_file<Row: ToTabDelimited>(
+    tables: &InternerTables,
+    rows: &[Row],
+    path: &Path,
+) -> io::Result<()> {
+    let mut stream = BufWriter::new(File::create(path)?);
+    let mut columns = vec![];
+    for row in rows {
+        columns.clear();
+        row.push_columns(tables, &mut columns);
+        writeln!(stream, "{}", columns.join("\t"))?;
+    }
+    stream.flush()
+}
+
+trait ToTabDelimited {
+    /// Pushes the names of the atoms of this row onto `columns`.
+    fn push_columns(&self, tables: &'t InternerTables, columns: &mut Vec<&'t str>);
+}
 
-        if columns.next().is_some() {
-            eprintln!("extra data on line {} of `{}`", index + 1, path.display());
-            process::exit(1);
+macro_rules! to_tab_delimited_atom {
+    ($t:ident, $field:ident) => {
+        impl ToTabDelimited for $t {
+            fn push_columns(&self, tables: &'t InternerTables, columns: &mut Vec<&'t str>) {
+                columns.push(tables.$field.untern(*self));
+            }
         }
+    };
+}
 
-        result.push(row);
+to_tab_delimited_atom!(Region, regions);
+to_tab_delimited_atom!(Loan, loans);
+to_tab_delimited_atom!(Point, points);
+to_tab_delimited_atom!(Variable, variables);
+to_tab_delimited_atom!(MovePath, paths);
+
+impl<A: ToTabDelimited, B: ToTabDelimited> ToTabDelimited for (A, B) {
+    fn push_columns(&self, tables: &'t InternerTables, columns: &mut Vec<&'t str>) {
+        self.0.push_columns(tables, columns);
+        self.1.push_columns(tables, columns);
+    }
+}
+
+impl<A: ToTabDelimited, B: ToTabDelimited, C: ToTabDelimited> ToTabDelimited for (A, B, C) {
+    fn push_columns(&self, tables: &'t InternerTables, columns: &mut Vec<&'t str>) {
+        self.0.push_columns(tables, columns);
+        self.1.push_columns(tables, columns);
+        self.2.push_columns(tables, columns);
+    }
+}
+
+impl<A, B, C, D> ToTabDelimited for (A, B, C, D)
+where
+    A: ToTabDelimited,
+    B: ToTabDelimited,
+    C: ToTabDelimited,
+    D: ToTabDelimited,
+{
+    fn push_columns(&self, tables: &'t InternerTables, columns: &mut Vec<&'t str>) {
+        self.0.push_columns(tables, columns);
+        self.1.push_columns(tables, columns);
+        self.2.push_columns(tables, columns);
+        self.3.push_columns(tables, columns);
     }
-    Ok(result)
 }
 
+/// The access and loan kinds are written by name, and parsed back
+/// case-insensitively, instead of being interned.
+macro_rules! tab_delimited_kind {
+    ($t:ident) => {
+        impl FromTabDelimited<'input> for $t {
+            const COLUMNS: usize = 1;
+
+            fn parse(
+                _tables: &mut InternerTables,
+                inputs: &mut dyn Iterator<Item = &'input str>,
+            ) -> Option<Self> {
+                inputs.next()?.parse().ok()
+            }
+        }
+
+        impl ToTabDelimited for $t {
+            fn push_columns(&self, _tables: &'t InternerTables, columns: &mut Vec<&'t str>) {
+                columns.push(self.name());
+            }
+        }
+    };
+}
+
+tab_delimited_kind!(AccessKind);
+tab_delimited_kind!(LoanKind);
+
 impl<T> FromTabDelimited<'input> for T
 where
     &'input str: InternTo<T>,
 {
+    const COLUMNS: usize = 1;
+
     fn parse(
         tables: &mut InternerTables,
         inputs: &mut dyn Iterator<Item = &'input str>,
@@ -89,6 +400,8 @@ where
     A: FromTabDelimited<'input>,
     B: FromTabDelimited<'input>,
 {
+    const COLUMNS: usize = A::COLUMNS + B::COLUMNS;
+
     fn parse(
         tables: &mut InternerTables,
         inputs: &mut dyn Iterator<Item = &'input str>,
@@ -105,6 +418,8 @@ where
     B: FromTabDelimited<'input>,
     C: FromTabDelimited<'input>,
 {
+    const COLUMNS: usize = A::COLUMNS + B::COLUMNS + C::COLUMNS;
+
     fn parse(
         tables: &mut InternerTables,
         inputs: &mut dyn Iterator<Item = &'input str>,
@@ -123,6 +438,8 @@ where
     C: FromTabDelimited<'input>,
     D: FromTabDelimited<'input>,
 {
+    const COLUMNS: usize = A::COLUMNS + B::COLUMNS + C::COLUMNS + D::COLUMNS;
+
     fn parse(
         tables: &mut InternerTables,
         inputs: &mut dyn Iterator<Item = &'input str>,
@@ -1,13 +1,30 @@
 #![cfg(test)]
 
-use crate::cli::Algorithm;
-use crate::facts::{Loan, Point};
+use crate::binary;
+use crate::builder::{assert_errors, assert_move_errors, errors_by_name, FactsBuilder};
+use crate::cli;
+use crate::compare;
+use crate::dump;
+use crate::explain::{self, Explainer, Step};
+use crate::facts::{Loan, Location, MovePath, Point, PointKind, Region};
 use crate::intern;
-use crate::output::Output;
+use crate::output::tracking::RegionDegrees;
+use crate::reduce::{self, Goal};
+use crate::stats;
 use crate::tab_delim;
+use crate::validate::{self, Problem};
 use failure::Error;
 use fxhash::FxHashMap;
-use std::path::Path;
+use histo::Histogram;
+use polonius_engine::{AccessKind, Algorithm, LoanKind, Output};
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 fn test_fn(dir_name: &str, fn_name: &str) -> Result<(), Error> {
     do catch {
@@ -22,6 +39,15 @@ fn test_fn(dir_name: &str, fn_name: &str) -> Result<(), Error> {
         let naive = Output::compute(&all_facts, Algorithm::Naive, false);
         let opt = Output::compute(&all_facts, Algorithm::DatafrogOpt, true);
         assert_eq!(naive.borrow_live_at, opt.borrow_live_at);
+        assert_eq!(naive.potential_errors, opt.potential_errors);
+
+        let hybrid = Output::compute(&all_facts, Algorithm::Hybrid, false);
+        assert_eq!(hybrid.potential_errors, opt.potential_errors);
+        assert!(compare::compare_outputs(
+            (Algorithm::DatafrogOpt, &opt),
+            (Algorithm::Hybrid, &hybrid),
+            Some(tables),
+        ));
     }
 }
 
@@ -40,6 +66,37 @@ tests! {
     issue_47680("issue-47680", "main"),
 }
 
+#[test]
+fn test_hybrid_independent_of_dump() {
+    let without_errors = FactsBuilder::new()
+        .path(&["a", "b", "c"])
+        .borrow_region("'x", "L0", "a")
+        .region_live_at("'x", &["a", "c"]);
+    let with_errors = FactsBuilder::new()
+        .path(&["a", "b", "c"])
+        .borrow_region("'x", "L0", "a")
+        .borrow_region("'y", "L1", "a")
+        .region_live_at("'x", &["a", "b", "c"])
+        .region_live_at("'y", &["a"])
+        .invalidates("c", "L0");
+
+    for builder in vec![without_errors, with_errors] {
+        let (_, all_facts) = builder.build();
+        let dumped = Output::compute(&all_facts, Algorithm::Hybrid, true);
+        let not_dumped = Output::compute(&all_facts, Algorithm::Hybrid, false);
+        assert_eq!(dumped.potential_errors, not_dumped.potential_errors);
+        assert_eq!(dumped.borrow_live_at, not_dumped.borrow_live_at);
+
+        let opt = Output::compute(&all_facts, Algorithm::DatafrogOpt, false);
+        assert_eq!(dumped.potential_errors, opt.potential_errors);
+        for (point, loans) in &dumped.borrow_live_at {
+            for loan in loans {
+                assert!(opt.borrow_live_at[point].contains(loan));
+            }
+        }
+    }
+}
+
 #[test]
 fn test_insensitive_potential_error() -> Result<(), Error> {
     do catch {
@@ -52,12 +109,11 @@ fn test_insensitive_potential_error() -> Result<(), Error> {
         let tables = &mut intern::InternerTables::new();
         let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
         let insensitive = Output::compute(&all_facts, Algorithm::LocationInsensitive, false);
-
-        let mut expected = FxHashMap::default();
-        expected.insert(Point::from(1), vec![Loan::from(1)]);
-        expected.insert(Point::from(2), vec![Loan::from(2)]);
-
-        assert_eq!(insensitive.potential_errors, expected);
+        assert_errors(
+            tables,
+            &insensitive,
+            &[("\"Mid(bb3[2])\"", "\"bw1\""), ("\"Mid(bb10[2])\"", "\"bw2\"")],
+        );
     }
 }
 
@@ -76,3 +132,960 @@ fn test_sensitive_passes_issue_47680() -> Result<(), Error> {
         assert!(sensitive.potential_errors.is_empty());
     }
 }
+
+/// A fresh directory for a test to write files in, removed when
+/// dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> io::Result<Self> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let unique = format!(
+            "polonius-test-{}-{}-{}",
+            name,
+            process::id(),
+            COUNT.fetch_add(1, Ordering::SeqCst)
+        );
+        let path = env::temp_dir().join(unique);
+        fs::create_dir_all(&path)?;
+        Ok(TempDir(path))
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn test_malformed_line_is_reported() -> Result<(), Error> {
+    do catch {
+        let facts_dir = TempDir::new("malformed-line")?;
+        fs::write(
+            facts_dir.join("borrow_region.facts"),
+            "\"'_#1r\"\t\"bw0\"\t\"Mid(bb0[0])\"\n\"'_#2r\"\t\"bw1\"\n",
+        )?;
+
+        let tables = &mut intern::InternerTables::new();
+        let error = match tab_delim::load_tab_delimited_facts(tables, &facts_dir) {
+            Ok(_) => panic!("malformed facts were loaded"),
+            Err(error) => error,
+        };
+        let parse_error = error
+            .get_ref()
+            .and_then(|error| error.downcast_ref::<tab_delim::ParseError>())
+            .expect("not a parse error");
+
+        assert_eq!(parse_error.line, 2);
+        assert_eq!(parse_error.column, 3);
+        assert_eq!(
+            parse_error.kind,
+            tab_delim::ParseErrorKind::WrongColumnCount {
+                expected: 3,
+                found: 2
+            }
+        );
+    }
+}
+
+#[test]
+fn test_missing_fact_files_are_empty() -> Result<(), Error> {
+    do catch {
+        let facts_dir = TempDir::new("missing-fact-files")?;
+        fs::write(
+            facts_dir.join("cfg_edge.facts"),
+            "\"Start(bb0[0])\"\t\"Mid(bb0[0])\"\n",
+        )?;
+
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+        assert_eq!(all_facts.cfg_edge.len(), 1);
+        assert!(all_facts.region_live_at.is_empty());
+        assert!(all_facts.invalidates.is_empty());
+
+        assert!(tab_delim::check_core_facts(&facts_dir).is_err());
+    }
+}
+
+#[test]
+fn test_parse_point_location() {
+    let start = Location::parse("\"Start(bb3[2])\"").unwrap();
+    assert_eq!(start.kind, PointKind::Start);
+    assert_eq!(start.block, 3);
+    assert_eq!(start.statement, 2);
+    assert_eq!(start.to_string(), "Start(bb3[2])");
+
+    let mid = Location::parse("Mid(bb10[1])").unwrap();
+    assert_eq!(mid.kind, PointKind::Mid);
+    assert!(start < mid);
+    assert!(Location::parse("Mid(bb3[2])").unwrap() < mid);
+    assert!(Location::parse("Start(bb3[2])").unwrap() < Location::parse("Mid(bb3[2])").unwrap());
+
+    assert!(Location::parse("bw0").is_none());
+    assert!(Location::parse("Mid(bb3)").is_none());
+
+    // the locations are parsed once, when the points are interned
+    let tables = &mut intern::InternerTables::new();
+    let point = tables.points.intern("\"Mid(bb10[1])\"");
+    let not_a_location = tables.points.intern("a");
+    assert_eq!(tables.point_location(point), Some(mid));
+    assert_eq!(tables.point_location(not_a_location), None);
+}
+
+#[test]
+fn test_explain_issue_47680() -> Result<(), Error> {
+    do catch {
+        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("issue-47680")
+            .join("nll-facts")
+            .join("main");
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+        let explainer = Explainer::new(&all_facts);
+
+        // `bw0` is live at this point, so it has a derivation that
+        // starts where it is issued and ends at that point.
+        let point = tables.points.intern("\"Mid(bb3[2])\"");
+        let steps = explainer
+            .explain(tables.loans.intern("\"bw0\""), point)
+            .expect("no derivation");
+        match steps[0] {
+            Step::Borrow { .. } => {}
+            step => panic!("derivation starts with {:?}", step),
+        }
+        match steps[steps.len() - 1] {
+            Step::Flow { to, .. } => assert_eq!(to, point),
+            step => panic!("derivation ends with {:?}", step),
+        }
+
+        // The location-insensitive analysis reports an error for `bw1`
+        // here, but it is spurious.
+        assert!(explainer.explain(tables.loans.intern("\"bw1\""), point).is_none());
+    }
+}
+
+/// A small input with at least one tuple in every relation, for the
+/// round trips through the fact formats.
+fn every_relation() -> FactsBuilder {
+    FactsBuilder::new()
+        .universal_region("'u")
+        .path(&["a", "b", "c"])
+        .borrow_region("'x", "L0", "a")
+        .killed("L0", "c")
+        .outlives("'x", "'u", "a")
+        .region_live_at("'x", &["a", "b"])
+        .invalidates("b", "L0")
+        .var_defined("v", "a")
+        .var_used("v", "b")
+        .var_drop_used("v", "c")
+        .var_uses_region("v", "'x")
+        .var_drops_region("v", "'u")
+        .child("m.f", "m")
+        .path_assigned_at("m", "a")
+        .path_moved_at("m.f", "b")
+        .path_accessed_at("m", "c")
+        .loan_path("L0", "m")
+        .loan_reserved_at("L0", "a")
+        .loan_activated_at("L0", "b")
+        .invalidates_by_read("b", "L0")
+        .invalidates_by_access("c", "L0", AccessKind::StorageDead, LoanKind::Shallow)
+}
+
+#[test]
+fn test_binary_facts_round_trip() -> Result<(), Error> {
+    do catch {
+        let (tables, all_facts) = every_relation().build();
+        let relations = &stats::input_stats(&all_facts, &tables)["relations"];
+        for (name, size) in relations.as_object().unwrap() {
+            assert_ne!(size, 0, "`{}` is empty", name);
+        }
+
+        let binary_dir = TempDir::new("binary-round-trip")?;
+        binary::write_binary_facts(&tables, &all_facts, &binary_dir)?;
+
+        let binary_tables = &mut intern::InternerTables::new();
+        let binary_facts = binary::load_binary_facts(binary_tables, &binary_dir)?;
+
+        // the names are interned in the same order, so the atoms match
+        assert_eq!(all_facts, binary_facts);
+        for point in all_facts.cfg_edge.iter().map(|&(p, _)| p) {
+            assert_eq!(tables.points.untern(point), binary_tables.points.untern(point));
+        }
+
+        // a corrupt count of regions, after the magic bytes and version,
+        // is an error rather than a huge allocation
+        let binary_file = binary_dir.join(binary::BINARY_FACTS_FILE);
+        let mut bytes = fs::read(&binary_file)?;
+        for byte in &mut bytes[8..12] {
+            *byte = 0xff;
+        }
+        fs::write(&binary_file, bytes)?;
+        let corrupt_tables = &mut intern::InternerTables::new();
+        match binary::load_binary_facts(corrupt_tables, &binary_dir) {
+            Ok(_) => panic!("corrupt binary facts were loaded"),
+            Err(error) => assert_eq!(error.kind(), io::ErrorKind::InvalidData),
+        }
+    }
+}
+
+#[test]
+fn test_profile_issue_47680() -> Result<(), Error> {
+    do catch {
+        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("issue-47680")
+            .join("nll-facts")
+            .join("main");
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+        let opt = Output::compute(&all_facts, Algorithm::DatafrogOpt, false);
+        let profiled = Output::compute_profiled(&all_facts, false);
+        assert_eq!(profiled.borrow_live_at, opt.borrow_live_at);
+        assert_eq!(profiled.potential_errors, opt.potential_errors);
+        assert!(opt.profile.is_none());
+
+        let profile = profiled.profile.unwrap();
+        assert!(profile.rounds > 0);
+        let rule = profile
+            .rules
+            .iter()
+            .find(|rule| rule.name == "borrow_live_at")
+            .unwrap();
+        assert_eq!(rule.applications, profile.rounds);
+        let variable = profile
+            .variables
+            .iter()
+            .find(|variable| variable.name == "borrow_live_at")
+            .unwrap();
+        assert!(variable.tuples > 0);
+        assert!(variable.last_changed <= profile.rounds);
+    }
+}
+
+#[test]
+fn test_region_degree_histograms() {
+    // 'a <= 'b and 'a <= 'c: 'a has an out-degree of 2, and 'b and 'c
+    // an in-degree of 1.
+    let mut degrees = RegionDegrees::new();
+    let p = Point::from(0);
+    degrees.update_degrees(Region::from(0), Region::from(1), p);
+    degrees.update_degrees(Region::from(0), Region::from(2), p);
+
+    assert_eq!(degrees.max_in_degree(), 1);
+    assert_eq!(degrees.max_out_degree(), 2);
+    assert!(!degrees.has_multidegree());
+
+    let (histo_in, histo_out) = degrees.histogram();
+    let counts = |histogram: &Histogram| -> Vec<(u64, u64)> {
+        histogram
+            .buckets()
+            .filter(|bucket| bucket.count() > 0)
+            .map(|bucket| (bucket.start(), bucket.count()))
+            .collect()
+    };
+    assert_eq!(counts(&histo_in), vec![(1, 2)]);
+    assert_eq!(counts(&histo_out), vec![(2, 1)]);
+}
+
+#[test]
+fn test_input_stats_issue_47680() -> Result<(), Error> {
+    do catch {
+        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("issue-47680")
+            .join("nll-facts")
+            .join("main");
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+        let stats = stats::input_stats(&all_facts, tables);
+
+        assert_eq!(stats["points"], 58);
+        assert_eq!(stats["basic_blocks"], 12);
+        assert_eq!(stats["universal_regions"], 2);
+        assert_eq!(stats["loans"], 3);
+        assert_eq!(stats["loops"], 1);
+        assert_eq!(stats["relations"]["cfg_edge"], 61);
+    }
+}
+
+#[test]
+fn test_validate_reports_problems() -> Result<(), Error> {
+    do catch {
+        let facts_dir = TempDir::new("validate")?;
+        fs::write(
+            facts_dir.join("cfg_edge.facts"),
+            concat!(
+                "\"Start(bb0[0])\"\t\"Mid(bb0[0])\"\n",
+                "\"Start(bb1[0])\"\t\"Mid(bb1[0])\"\n",
+                "\"Start(bb2[0])\"\t\"Mid(bb2[0])\"\n",
+                "\"Mid(bb2[0])\"\t\"Start(bb2[0])\"\n",
+            ),
+        )?;
+        fs::write(
+            facts_dir.join("borrow_region.facts"),
+            "\"'_#1r\"\t\"bw0\"\t\"Mid(bb0[0])\"\n",
+        )?;
+        fs::write(
+            facts_dir.join("killed.facts"),
+            "\"bw0\"\t\"Mid(bb0[0])\"\n\"bw0\"\t\"Mid(bb0[0])\"\n",
+        )?;
+        fs::write(
+            facts_dir.join("invalidates.facts"),
+            "\"Mid(bb9[0])\"\t\"bw1\"\n",
+        )?;
+
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+        let problems = validate::validate(&all_facts, tables);
+        let point = |tables: &mut intern::InternerTables, name| tables.points.intern(name);
+        let expected = vec![
+            Problem::Duplicates {
+                relation: "killed",
+                count: 1,
+            },
+            Problem::UnknownPoint {
+                relation: "invalidates",
+                point: point(tables, "\"Mid(bb9[0])\""),
+            },
+            Problem::UnknownLoan {
+                relation: "invalidates",
+                loan: tables.loans.intern("\"bw1\""),
+            },
+            Problem::NoPredecessors {
+                point: point(tables, "\"Start(bb1[0])\""),
+            },
+            Problem::Unreachable {
+                point: point(tables, "\"Start(bb2[0])\""),
+            },
+            Problem::Unreachable {
+                point: point(tables, "\"Mid(bb2[0])\""),
+            },
+        ];
+        assert_eq!(problems, expected);
+    }
+}
+
+#[test]
+fn test_reduce_disagreement_issue_47680() -> Result<(), Error> {
+    do catch {
+        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("issue-47680")
+            .join("nll-facts")
+            .join("main");
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+
+        // The location-insensitive analysis reports spurious errors here.
+        let goal = Goal::Disagreement(Algorithm::LocationInsensitive, Algorithm::DatafrogOpt);
+        assert!(goal.holds(&all_facts));
+
+        let reduced = reduce::reduce(all_facts.clone(), tables, |facts| goal.holds(facts));
+        assert!(goal.holds(&reduced));
+        assert!(reduce::tuple_count(&reduced) < reduce::tuple_count(&all_facts) / 4);
+    }
+}
+
+#[test]
+fn test_tab_delimited_facts_round_trip() -> Result<(), Error> {
+    do catch {
+        let facts_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("inputs")
+            .join("issue-47680")
+            .join("nll-facts")
+            .join("main");
+        let tables = &mut intern::InternerTables::new();
+        let all_facts = tab_delim::load_tab_delimited_facts(tables, &facts_dir)?;
+
+        let written_dir = TempDir::new("tab-delimited-round-trip")?;
+        tab_delim::write_tab_delimited_facts(tables, &all_facts, &written_dir)?;
+
+        // the files are byte-for-byte identical to rustc's
+        for entry in fs::read_dir(&facts_dir)? {
+            let path = entry?.path();
+            let written = fs::read(written_dir.join(path.file_name().unwrap()))?;
+            assert_eq!(fs::read(&path)?, written, "{} differs", path.display());
+        }
+
+        let written_tables = &mut intern::InternerTables::new();
+        let written_facts = tab_delim::load_tab_delimited_facts(written_tables, &written_dir)?;
+        assert_eq!(all_facts, written_facts);
+        assert_eq!(tables.points.len(), written_tables.points.len());
+    }
+}
+
+/// Checks that the location-sensitive algorithms report exactly the
+/// `expected` errors, and that the location-insensitive one reports at
+/// least those.
+fn check_errors(builder: FactsBuilder, expected: &[(&str, &str)]) {
+    let (tables, all_facts) = builder.build();
+    for &algorithm in &[Algorithm::Naive, Algorithm::DatafrogOpt, Algorithm::Hybrid] {
+        println!("algorithm = {:?}", algorithm);
+        let output = Output::compute(&all_facts, algorithm, false);
+        assert_errors(&tables, &output, expected);
+    }
+
+    let insensitive = Output::compute(&all_facts, Algorithm::LocationInsensitive, false);
+    let insensitive_errors = errors_by_name(&tables, &insensitive);
+    for &(point, loan) in expected {
+        assert!(insensitive_errors.contains(&(point.to_string(), loan.to_string())));
+    }
+}
+
+#[test]
+fn test_builder_borrow_live_when_invalidated() {
+    let builder = FactsBuilder::new()
+        .path(&["a", "b", "c"])
+        .borrow_region("'x", "L0", "a")
+        .region_live_at("'x", &["a", "b", "c"])
+        .invalidates("c", "L0");
+    check_errors(builder, &[("c", "L0")]);
+}
+
+#[test]
+fn test_json_has_errors_without_dump() {
+    let (tables, all_facts) = FactsBuilder::new()
+        .path(&["a", "b", "c"])
+        .borrow_region("'x", "L0", "a")
+        .region_live_at("'x", &["a", "b", "c"])
+        .invalidates("c", "L0")
+        .build();
+    let output = Output::compute(&all_facts, Algorithm::Naive, false);
+    let json = dump::output_to_json(&output, &tables);
+    assert_eq!(json["potential_errors"], json!([["c", "L0"]]));
+    assert!(json.get("subset").is_none());
+}
+
+#[test]
+fn test_builder_killed_borrow() {
+    let builder = FactsBuilder::new()
+        .path(&["a", "b", "c"])
+        .borrow_region("'x", "L0", "a")
+        .region_live_at("'x", &["a", "b", "c"])
+        .killed("L0", "b")
+        .invalidates("c", "L0");
+    check_errors(builder, &[]);
+}
+
+#[test]
+fn test_builder_dead_region() {
+    let builder = FactsBuilder::new()
+        .path(&["a", "b", "c"])
+        .borrow_region("'x", "L0", "a")
+        .region_live_at("'x", &["a", "b"])
+        .invalidates("c", "L0");
+    check_errors(builder, &[]);
+}
+
+#[test]
+fn test_builder_borrow_flows_into_live_region() {
+    // `'x` dies after `a`, but `'x: 'y` there and `'y` stays live.
+    let builder = FactsBuilder::new()
+        .path(&["a", "b", "c"])
+        .borrow_region("'x", "L0", "a")
+        .outlives("'x", "'y", "a")
+        .region_live_at("'x", &["a"])
+        .region_live_at("'y", &["a", "b", "c"])
+        .invalidates("c", "L0");
+    check_errors(builder, &[("c", "L0")]);
+}
+
+#[test]
+fn test_builder_killed_on_one_branch() {
+    // The loan is killed on the `b` branch only, so it is still live
+    // at `d` through `c`.
+    let builder = FactsBuilder::new()
+        .path(&["a", "b", "d"])
+        .path(&["a", "c", "d"])
+        .borrow_region("'x", "L0", "a")
+        .region_live_at("'x", &["a", "b", "c", "d"])
+        .killed("L0", "b")
+        .invalidates("d", "L0");
+    check_errors(builder, &[("d", "L0")]);
+}
+
+#[test]
+fn test_builder_liveness_from_variable_facts() {
+    // `v` holds the borrow and is used at `d`, so `'x` is live at `c`
+    // without any `region_live_at` fact.
+    let builder = FactsBuilder::new()
+        .path(&["a", "b", "c", "d"])
+        .borrow_region("'x", "L0", "a")
+        .var_defined("v", "a")
+        .var_uses_region("v", "'x")
+        .var_used("v", "d")
+        .invalidates("c", "L0");
+    check_errors(builder, &[("c", "L0")]);
+}
+
+#[test]
+fn test_builder_liveness_variable_overwritten() {
+    // `v` is overwritten at `c` before its use at `d`, so the borrow is
+    // dead by then.
+    let builder = FactsBuilder::new()
+        .path(&["a", "b", "c", "d"])
+        .borrow_region("'x", "L0", "a")
+        .var_defined("v", "a")
+        .var_defined("v", "c")
+        .var_uses_region("v", "'x")
+        .var_used("v", "d")
+        .invalidates("c", "L0");
+    check_errors(builder, &[]);
+}
+
+#[test]
+fn test_builder_liveness_drop() {
+    // `v` is only dropped at `d`, which needs `'x`: it is live until then.
+    let builder = FactsBuilder::new()
+        .path(&["a", "b", "c", "d"])
+        .borrow_region("'x", "L0", "a")
+        .var_defined("v", "a")
+        .var_drops_region("v", "'x")
+        .var_drop_used("v", "d")
+        .invalidates("c", "L0");
+    check_errors(builder, &[("c", "L0")]);
+}
+
+fn check_move_errors(builder: FactsBuilder, expected: &[(&str, &str)]) {
+    let (tables, all_facts) = builder.build();
+    let output = Output::compute(&all_facts, Algorithm::Naive, false);
+    assert_move_errors(&tables, &output, expected);
+}
+
+#[test]
+fn test_builder_use_after_move() {
+    let builder = FactsBuilder::new()
+        .path(&["a", "b", "c"])
+        .path_assigned_at("x", "a")
+        .path_moved_at("x", "b")
+        .path_accessed_at("x", "b")
+        .path_accessed_at("x", "c");
+    check_move_errors(builder, &[("c", "x")]);
+}
+
+#[test]
+fn test_builder_reinitialized_after_move() {
+    let builder = FactsBuilder::new()
+        .path(&["a", "b", "c", "d"])
+        .path_assigned_at("x", "a")
+        .path_moved_at("x", "b")
+        .path_assigned_at("x", "c")
+        .path_accessed_at("x", "d");
+    check_move_errors(builder, &[]);
+}
+
+#[test]
+fn test_builder_moved_on_one_branch() {
+    let builder = FactsBuilder::new()
+        .path(&["a", "b", "d"])
+        .path(&["a", "c", "d"])
+        .path_assigned_at("x", "a")
+        .path_moved_at("x", "b")
+        .path_accessed_at("x", "d");
+    check_move_errors(builder, &[("d", "x")]);
+}
+
+#[test]
+fn test_builder_use_before_init() {
+    let builder = FactsBuilder::new()
+        .path(&["a", "b", "c"])
+        .path_assigned_at("arg", "a")
+        .path_accessed_at("arg", "b")
+        .path_accessed_at("x", "b")
+        .path_assigned_at("x", "b")
+        .path_accessed_at("x", "c");
+    check_move_errors(builder, &[("b", "x")]);
+}
+
+#[test]
+fn test_builder_use_before_init_at_entry() {
+    let builder = FactsBuilder::new()
+        .path(&["a", "b"])
+        .path_assigned_at("arg", "a")
+        .path_accessed_at("arg", "a")
+        .path_accessed_at("x", "a");
+    check_move_errors(builder, &[("a", "x")]);
+
+    // a single point, without any edge
+    let builder = FactsBuilder::new().path_accessed_at("x", "a");
+    check_move_errors(builder, &[("a", "x")]);
+}
+
+#[test]
+fn test_builder_partial_moves() {
+    // Moving `x.f` leaves `x.g` initialized, but not `x` as a whole;
+    // moving `y` moves `y.f` as well.
+    let builder = FactsBuilder::new()
+        .path(&["a", "b", "c"])
+        .child("x.f", "x")
+        .child("x.g", "x")
+        .child("y.f", "y")
+        .path_assigned_at("x", "a")
+        .path_assigned_at("y", "a")
+        .path_moved_at("x.f", "b")
+        .path_moved_at("y", "b")
+        .path_accessed_at("x.g", "c")
+        .path_accessed_at("x", "c")
+        .path_accessed_at("y.f", "c");
+    check_move_errors(builder, &[("c", "x.f"), ("c", "y.f")]);
+}
+
+#[test]
+fn test_derive_killed() {
+    let (tables, all_facts) = FactsBuilder::new()
+        .path(&["a", "b", "c"])
+        .child("x.f", "x")
+        .borrow_region("'x", "Lx", "a")
+        .borrow_region("'x", "Lxf", "a")
+        .borrow_region("'y", "Ly", "a")
+        .loan_path("Lx", "x")
+        .loan_path("Lxf", "x.f")
+        .loan_path("Ly", "y")
+        .path_assigned_at("x", "b")
+        .path_assigned_at("x.f", "c")
+        .build();
+
+    let mut killed: Vec<_> = polonius_engine::derive_killed(&all_facts)
+        .into_iter()
+        .map(|(loan, point)| (tables.loans.untern(loan), tables.points.untern(point)))
+        .collect();
+    killed.sort();
+    assert_eq!(killed, [("Lx", "b"), ("Lxf", "b"), ("Lxf", "c")]);
+}
+
+#[test]
+fn test_validate_cross_checks_killed() {
+    let (mut tables, all_facts) = FactsBuilder::new()
+        .path(&["a", "b", "c"])
+        .borrow_region("'x", "L0", "a")
+        .loan_path("L0", "x")
+        .path_assigned_at("x", "b")
+        .killed("L0", "c")
+        .build();
+
+    let problems = validate::validate(&all_facts, &tables);
+    let loan = tables.loans.intern("L0");
+    let expected = vec![
+        Problem::UnexpectedKill {
+            loan,
+            point: tables.points.intern("c"),
+        },
+        Problem::MissingKill {
+            loan,
+            point: tables.points.intern("b"),
+        },
+    ];
+    assert_eq!(problems, expected);
+}
+
+/// A two-phase borrow `L0` of some `v`, reserved at `a` and activated
+/// at `c`, e.g. `v.push(v.len())`.
+fn two_phase_borrow() -> FactsBuilder {
+    FactsBuilder::new()
+        .path(&["a", "b", "c", "d"])
+        .borrow_region("'x", "L0", "a")
+        .region_live_at("'x", &["a", "b", "c", "d"])
+        .loan_reserved_at("L0", "a")
+        .loan_activated_at("L0", "c")
+}
+
+#[test]
+fn test_two_phase_read_before_activation() {
+    check_errors(two_phase_borrow().invalidates_by_read("b", "L0"), &[]);
+}
+
+#[test]
+fn test_two_phase_write_before_activation() {
+    check_errors(two_phase_borrow().invalidates("b", "L0"), &[("b", "L0")]);
+}
+
+#[test]
+fn test_two_phase_read_after_activation() {
+    check_errors(two_phase_borrow().invalidates_by_read("d", "L0"), &[("d", "L0")]);
+}
+
+#[test]
+fn test_two_phase_activated_on_one_branch() {
+    let builder = FactsBuilder::new()
+        .path(&["a", "b", "d"])
+        .path(&["a", "c", "d"])
+        .borrow_region("'x", "L0", "a")
+        .region_live_at("'x", &["a", "b", "c", "d"])
+        .loan_reserved_at("L0", "a")
+        .loan_activated_at("L0", "b")
+        .invalidates_by_read("c", "L0")
+        .invalidates_by_read("d", "L0");
+    check_errors(builder, &[("d", "L0")]);
+}
+
+/// A loan `L0` of kind `kind`, issued at `a` and live until `c`.
+fn loan_of_kind(kind: LoanKind, access: AccessKind) -> FactsBuilder {
+    FactsBuilder::new()
+        .path(&["a", "b", "c"])
+        .borrow_region("'x", "L0", "a")
+        .region_live_at("'x", &["a", "b", "c"])
+        .invalidates_by_access("b", "L0", access, kind)
+}
+
+#[test]
+fn test_access_read_of_shared_loan() {
+    check_errors(loan_of_kind(LoanKind::Shared, AccessKind::Read), &[]);
+    check_errors(loan_of_kind(LoanKind::Shallow, AccessKind::Read), &[]);
+}
+
+#[test]
+fn test_access_write_of_shared_loan() {
+    check_errors(loan_of_kind(LoanKind::Shared, AccessKind::DeepWrite), &[("b", "L0")]);
+    check_errors(loan_of_kind(LoanKind::Shallow, AccessKind::ShallowWrite), &[("b", "L0")]);
+}
+
+#[test]
+fn test_access_error_kinds() {
+    let (mut tables, all_facts) = loan_of_kind(LoanKind::Mutable, AccessKind::Read)
+        .invalidates_by_access("b", "L0", AccessKind::StorageDead, LoanKind::Mutable)
+        .build();
+    let output = Output::compute(&all_facts, Algorithm::Naive, false);
+    assert_errors(&tables, &output, &[("b", "L0")]);
+
+    let point = tables.points.intern("b");
+    let loan = tables.loans.intern("L0");
+    assert_eq!(output.potential_errors[&point], vec![loan]);
+    assert_eq!(
+        output.error_kinds_at(point, loan),
+        vec![
+            (AccessKind::Read, LoanKind::Mutable),
+            (AccessKind::StorageDead, LoanKind::Mutable),
+        ]
+    );
+}
+
+#[test]
+fn test_two_phase_access_before_activation() {
+    let read = two_phase_borrow().invalidates_by_access(
+        "b",
+        "L0",
+        AccessKind::Read,
+        LoanKind::Mutable,
+    );
+    check_errors(read, &[]);
+
+    // a write at the same point still conflicts
+    let read_and_write = two_phase_borrow()
+        .invalidates_by_access("b", "L0", AccessKind::Read, LoanKind::Mutable)
+        .invalidates_by_access("b", "L0", AccessKind::ShallowWrite, LoanKind::Mutable);
+    check_errors(read_and_write, &[("b", "L0")]);
+}
+
+/// The last lines of the explanations of the errors of `builder`,
+/// naming the facts by which the loans are invalidated.
+fn explained_conflicts(builder: FactsBuilder) -> Vec<String> {
+    let (tables, all_facts) = builder.build();
+    let output = Output::compute(&all_facts, Algorithm::Naive, false);
+    let mut explanation = vec![];
+    explain::explain_errors(&all_facts, &output, &tables, &mut explanation).unwrap();
+    String::from_utf8(explanation)
+        .unwrap()
+        .lines()
+        .filter(|line| line.starts_with("    invalidates"))
+        .map(|line| line.trim().to_string())
+        .collect()
+}
+
+#[test]
+fn test_explain_conflicts() {
+    let access = loan_of_kind(LoanKind::Mutable, AccessKind::Read);
+    assert_eq!(
+        explained_conflicts(access),
+        vec!["invalidates_by_access(b, L0, Read, Mutable)"]
+    );
+
+    let read = two_phase_borrow().invalidates_by_read("d", "L0");
+    assert_eq!(
+        explained_conflicts(read),
+        vec!["invalidates_by_read(d, L0) with loan_activated_at(L0, c)"]
+    );
+
+    let write = two_phase_borrow().invalidates("b", "L0");
+    assert_eq!(
+        explained_conflicts(write),
+        vec!["invalidates(b, L0) with loan_reserved_at(L0, a)"]
+    );
+}
+
+#[test]
+fn test_access_kinds_round_trip() -> Result<(), Error> {
+    do catch {
+        let (tables, all_facts) =
+            loan_of_kind(LoanKind::Shallow, AccessKind::ShallowWrite).build();
+        let facts_dir = TempDir::new("access-kinds-round-trip")?;
+        tab_delim::write_tab_delimited_facts(&tables, &all_facts, &facts_dir)?;
+
+        let loaded_tables = &mut intern::InternerTables::new();
+        let loaded = tab_delim::load_tab_delimited_facts(loaded_tables, &facts_dir)?;
+        let access = &loaded.invalidates_by_access;
+        assert_eq!(access.len(), 1);
+        assert_eq!((access[0].2, access[0].3), (AccessKind::ShallowWrite, LoanKind::Shallow));
+
+        // an unknown kind is reported as invalid data
+        fs::write(facts_dir.join("invalidates_by_access.facts"), "b\tL0\tRead\tUnique\n")?;
+        let tables = &mut intern::InternerTables::new();
+        let error = match tab_delim::load_tab_delimited_facts(tables, &facts_dir) {
+            Ok(_) => panic!("an unknown loan kind was loaded"),
+            Err(error) => error,
+        };
+        let parse_error = error
+            .get_ref()
+            .and_then(|error| error.downcast_ref::<tab_delim::ParseError>())
+            .expect("not a parse error");
+        assert_eq!(parse_error.line, 1);
+        assert_eq!(parse_error.column, 4);
+        assert_eq!(parse_error.text, "Unique");
+        assert_eq!(parse_error.kind, tab_delim::ParseErrorKind::InvalidValue);
+    }
+}
+
+/// Set this environment variable to overwrite the expected outputs of
+/// `test_snapshots` with the current ones.
+const BLESS_VAR: &str = "POLONIUS_BLESS";
+
+/// Runs each algorithm on each fact directory under `inputs/`, and
+/// compares its `borrow_live_at` and `potential_errors` with the ones
+/// in `snapshots/`, which mirrors `inputs/`.
+#[test]
+fn test_snapshots() -> Result<(), Error> {
+    do catch {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let inputs_dir = root.join("inputs");
+        let bless = env::var_os(BLESS_VAR).is_some();
+
+        let mut facts_dirs = vec![];
+        find_facts_dirs(&inputs_dir, &mut facts_dirs)?;
+        assert!(!facts_dirs.is_empty());
+
+        let mut failures = vec![];
+        for facts_dir in facts_dirs {
+            let tables = &mut intern::InternerTables::new();
+            let all_facts = cli::load_facts(tables, &facts_dir, false)?;
+            let snapshot_dir = root
+                .join("snapshots")
+                .join(facts_dir.strip_prefix(&inputs_dir).unwrap());
+
+            for name in Algorithm::variants().iter() {
+                let algorithm: Algorithm = name.parse().unwrap();
+                let output = Output::compute(&all_facts, algorithm, false);
+                let actual = snapshot(tables, &output);
+                let path = snapshot_dir.join(format!("{}.txt", name));
+
+                if bless {
+                    fs::create_dir_all(&snapshot_dir)?;
+                    fs::write(&path, &actual)?;
+                    continue;
+                }
+
+                match fs::read_to_string(&path) {
+                    Ok(ref expected) if *expected == actual => {}
+                    Ok(expected) => failures.push(format!(
+                        "`{}` differs:\n{}",
+                        path.display(),
+                        snapshot_diff(&expected, &actual)
+                    )),
+                    Err(error) => failures.push(format!("`{}`: {}", path.display(), error)),
+                }
+            }
+        }
+
+        assert!(
+            failures.is_empty(),
+            "{}\nrun the tests with {}=1 to accept the new outputs",
+            failures.join("\n"),
+            BLESS_VAR
+        );
+    }
+}
+
+/// Collects the directories under `dir` that contain `.facts` files.
+fn find_facts_dirs(dir: &Path, facts_dirs: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    paths.sort();
+
+    let mut has_facts = false;
+    for path in paths {
+        if path.is_dir() {
+            find_facts_dirs(&path, facts_dirs)?;
+        } else if path.extension().map_or(false, |extension| extension == "facts") {
+            has_facts = true;
+        }
+    }
+
+    if has_facts {
+        facts_dirs.push(dir.to_owned());
+    }
+    Ok(())
+}
+
+/// Renders the relations checked by `test_snapshots`, with the names
+/// of the atoms, sorted by location.
+fn snapshot(
+    tables: &intern::InternerTables,
+    output: &Output<Region, Loan, Point, MovePath>,
+) -> String {
+    let relations = [
+        ("borrow_live_at", &output.borrow_live_at),
+        ("potential_errors", &output.potential_errors),
+    ];
+
+    let mut result = String::new();
+    for &(name, relation) in &relations {
+        writeln!(result, "# {}", name).unwrap();
+
+        let mut rows: Vec<(&str, &str, Option<Location>)> = relation
+            .iter()
+            .flat_map(|(&point, loans)| {
+                loans.iter().map(move |&loan| {
+                    (
+                        tables.points.untern(point),
+                        tables.loans.untern(loan),
+                        tables.point_location(point),
+                    )
+                })
+            })
+            .collect();
+        rows.sort_by_key(|&(point, loan, location)| (location, point, loan));
+        for (point, loan, _) in rows {
+            writeln!(result, "{}\t{}", point, loan).unwrap();
+        }
+    }
+    result
+}
+
+/// The lines only in `expected` (prefixed with `-`) or only in
+/// `actual` (prefixed with `+`).
+fn snapshot_diff(expected: &str, actual: &str) -> String {
+    let expected_lines: Vec<&str> = expected.lines().collect();
+    let actual_lines: Vec<&str> = actual.lines().collect();
+
+    let mut diff = String::new();
+    for line in &expected_lines {
+        if !actual_lines.contains(line) {
+            writeln!(diff, "- {}", line).unwrap();
+        }
+    }
+    for line in &actual_lines {
+        if !expected_lines.contains(line) {
+            writeln!(diff, "+ {}", line).unwrap();
+        }
+    }
+    diff
+}
@@ -0,0 +1,340 @@
+//! Checks that the facts of an input are consistent with each other,
+//! to tell apart bugs in the fact generation from bugs in the
+//! analyses: the analyses silently accept inconsistent facts, and just
+//! compute something surprising.
+
+use crate::facts::{AllFacts, Loan, Point, Region};
+use crate::intern::InternerTables;
+use fxhash::{FxHashMap, FxHashSet};
+use std::collections::BTreeSet;
+use std::hash::Hash;
+
+/// Something suspicious in the facts of an input.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+crate enum Problem {
+    /// A tuple of `relation` mentions a point absent from `cfg_edge`.
+    UnknownPoint { relation: &'static str, point: Point },
+
+    /// A tuple of `relation` mentions a loan never issued in `borrow_region`.
+    UnknownLoan { relation: &'static str, loan: Loan },
+
+    /// `outlives(from, to, point)` between two universal regions: those
+    /// are only related by the function's signature, not at some point.
+    UniversalOutlives { from: Region, to: Region, point: Point },
+
+    /// `relation` contains `count` tuples more than once.
+    Duplicates { relation: &'static str, count: usize },
+
+    /// A point with no predecessors in `cfg_edge`, other than the entry
+    /// (the first one by location).
+    NoPredecessors { point: Point },
+
+    /// A point that can't be reached from any point without
+    /// predecessors, i.e. that is only reachable from a detached cycle.
+    Unreachable { point: Point },
+
+    /// `killed(loan, point)` is not among the kills derived from the
+    /// move path facts, see `polonius_engine::derive_killed`.
+    UnexpectedKill { loan: Loan, point: Point },
+
+    /// A kill derived from the move path facts is missing from `killed`.
+    MissingKill { loan: Loan, point: Point },
+}
+
+impl Problem {
+    crate fn describe(&self, tables: &InternerTables) -> String {
+        let point_name = |point| tables.points.untern(point);
+        match *self {
+            Problem::UnknownPoint { relation, point } => format!(
+                "`{}` mentions {}, which is not in `cfg_edge`",
+                relation,
+                point_name(point)
+            ),
+            Problem::UnknownLoan { relation, loan } => format!(
+                "`{}` mentions {}, which is not created in `borrow_region`",
+                relation,
+                tables.loans.untern(loan)
+            ),
+            Problem::UniversalOutlives { from, to, point } => format!(
+                "`outlives` relates the universal regions {} and {} at {}",
+                tables.regions.untern(from),
+                tables.regions.untern(to),
+                point_name(point)
+            ),
+            Problem::Duplicates { relation, count } => {
+                format!("`{}` has {} duplicate tuple(s)", relation, count)
+            }
+            Problem::NoPredecessors { point } => format!(
+                "{} has no predecessors, but is not the entry point",
+                point_name(point)
+            ),
+            Problem::Unreachable { point } => {
+                format!("{} is unreachable from the entry point", point_name(point))
+            }
+            Problem::UnexpectedKill { loan, point } => format!(
+                "`killed` kills {} at {}, but no prefix of its `loan_path` is assigned there",
+                tables.loans.untern(loan),
+                point_name(point)
+            ),
+            Problem::MissingKill { loan, point } => format!(
+                "a prefix of the `loan_path` of {} is assigned at {}, but it is not `killed` there",
+                tables.loans.untern(loan),
+                point_name(point)
+            ),
+        }
+    }
+}
+
+/// Returns the problems found in `all_facts`, in a deterministic order.
+crate fn validate(all_facts: &AllFacts, tables: &InternerTables) -> Vec<Problem> {
+    let mut problems = vec![];
+
+    macro_rules! check_duplicates {
+        ($($t:ident,)*) => {
+            $(
+                let count = count_duplicates(&all_facts.$t);
+                if count > 0 {
+                    problems.push(Problem::Duplicates { relation: stringify!($t), count });
+                }
+            )*
+        }
+    }
+
+    check_duplicates! {
+        borrow_region,
+        universal_region,
+        cfg_edge,
+        killed,
+        outlives,
+        region_live_at,
+        invalidates,
+        var_defined,
+        var_used,
+        var_drop_used,
+        var_uses_region,
+        var_drops_region,
+        child,
+        path_assigned_at,
+        path_moved_at,
+        path_accessed_at,
+        loan_path,
+        loan_reserved_at,
+        loan_activated_at,
+        invalidates_by_read,
+        invalidates_by_access,
+    }
+
+    // dangling references to points and loans
+    let cfg_points: FxHashSet<Point> = all_facts
+        .cfg_edge
+        .iter()
+        .flat_map(|&(p, q)| vec![p, q])
+        .collect();
+    let mut unknown_points = BTreeSet::new();
+    {
+        let mut check_point = |relation, point| {
+            if !cfg_points.contains(&point) {
+                unknown_points.insert((relation, tables.point_location(point), point));
+            }
+        };
+        for &(_, _, p) in &all_facts.borrow_region {
+            check_point("borrow_region", p);
+        }
+        for &(_, p) in &all_facts.killed {
+            check_point("killed", p);
+        }
+        for &(_, _, p) in &all_facts.outlives {
+            check_point("outlives", p);
+        }
+        for &(_, p) in &all_facts.region_live_at {
+            check_point("region_live_at", p);
+        }
+        for &(p, _) in &all_facts.invalidates {
+            check_point("invalidates", p);
+        }
+        for &(_, p) in &all_facts.var_defined {
+            check_point("var_defined", p);
+        }
+        for &(_, p) in &all_facts.var_used {
+            check_point("var_used", p);
+        }
+        for &(_, p) in &all_facts.var_drop_used {
+            check_point("var_drop_used", p);
+        }
+        for &(_, p) in &all_facts.path_assigned_at {
+            check_point("path_assigned_at", p);
+        }
+        for &(_, p) in &all_facts.path_moved_at {
+            check_point("path_moved_at", p);
+        }
+        for &(_, p) in &all_facts.path_accessed_at {
+            check_point("path_accessed_at", p);
+        }
+        for &(_, p) in &all_facts.loan_reserved_at {
+            check_point("loan_reserved_at", p);
+        }
+        for &(_, p) in &all_facts.loan_activated_at {
+            check_point("loan_activated_at", p);
+        }
+        for &(p, _) in &all_facts.invalidates_by_read {
+            check_point("invalidates_by_read", p);
+        }
+        for &(p, _, _, _) in &all_facts.invalidates_by_access {
+            check_point("invalidates_by_access", p);
+        }
+    }
+    problems.extend(
+        unknown_points
+            .into_iter()
+            .map(|(relation, _, point)| Problem::UnknownPoint { relation, point }),
+    );
+
+    let issued_loans: FxHashSet<Loan> =
+        all_facts.borrow_region.iter().map(|&(_, b, _)| b).collect();
+    let mut unknown_loans = BTreeSet::new();
+    for &(b, _) in &all_facts.killed {
+        if !issued_loans.contains(&b) {
+            unknown_loans.insert(("killed", b));
+        }
+    }
+    for &(_, b) in &all_facts.invalidates {
+        if !issued_loans.contains(&b) {
+            unknown_loans.insert(("invalidates", b));
+        }
+    }
+    for &(b, _) in &all_facts.loan_path {
+        if !issued_loans.contains(&b) {
+            unknown_loans.insert(("loan_path", b));
+        }
+    }
+    for &(b, _) in &all_facts.loan_reserved_at {
+        if !issued_loans.contains(&b) {
+            unknown_loans.insert(("loan_reserved_at", b));
+        }
+    }
+    for &(b, _) in &all_facts.loan_activated_at {
+        if !issued_loans.contains(&b) {
+            unknown_loans.insert(("loan_activated_at", b));
+        }
+    }
+    for &(_, b) in &all_facts.invalidates_by_read {
+        if !issued_loans.contains(&b) {
+            unknown_loans.insert(("invalidates_by_read", b));
+        }
+    }
+    for &(_, b, _, _) in &all_facts.invalidates_by_access {
+        if !issued_loans.contains(&b) {
+            unknown_loans.insert(("invalidates_by_access", b));
+        }
+    }
+    problems.extend(
+        unknown_loans
+            .into_iter()
+            .map(|(relation, loan)| Problem::UnknownLoan { relation, loan }),
+    );
+
+    let universal_regions: FxHashSet<Region> =
+        all_facts.universal_region.iter().cloned().collect();
+    let is_universal = |region| universal_regions.contains(&region);
+    let mut universal_outlives: Vec<_> = all_facts
+        .outlives
+        .iter()
+        .filter(|&&(r1, r2, _)| is_universal(r1) && is_universal(r2))
+        .cloned()
+        .collect();
+    universal_outlives.sort_by_key(|&(r1, r2, p)| (tables.point_location(p), p, r1, r2));
+    universal_outlives.dedup();
+    problems.extend(
+        universal_outlives
+            .into_iter()
+            .map(|(from, to, point)| Problem::UniversalOutlives { from, to, point }),
+    );
+
+    problems.extend(check_cfg(all_facts, tables));
+
+    if !all_facts.loan_path.is_empty() {
+        problems.extend(check_killed(all_facts, tables));
+    }
+    problems
+}
+
+/// Compares `killed` with the kills derived from the move path facts.
+fn check_killed(all_facts: &AllFacts, tables: &InternerTables) -> Vec<Problem> {
+    let given: FxHashSet<(Loan, Point)> = all_facts.killed.iter().cloned().collect();
+    let derived: FxHashSet<(Loan, Point)> =
+        polonius_engine::derive_killed(all_facts).into_iter().collect();
+
+    let sorted = |kills: Vec<(Loan, Point)>| {
+        let mut kills = kills;
+        kills.sort_by_key(|&(b, p)| (tables.point_location(p), p, b));
+        kills
+    };
+    let unexpected = sorted(given.difference(&derived).cloned().collect());
+    let missing = sorted(derived.difference(&given).cloned().collect());
+
+    let mut problems: Vec<_> = unexpected
+        .into_iter()
+        .map(|(loan, point)| Problem::UnexpectedKill { loan, point })
+        .collect();
+    problems.extend(
+        missing
+            .into_iter()
+            .map(|(loan, point)| Problem::MissingKill { loan, point }),
+    );
+    problems
+}
+
+/// Finds the points without predecessors and the unreachable ones.
+/// The entry is the first point (by location) without predecessors;
+/// the points reachable only from the other ones are not reported, as
+/// the problem is with the latter.
+fn check_cfg(all_facts: &AllFacts, tables: &InternerTables) -> Vec<Problem> {
+    let mut successors: FxHashMap<Point, Vec<Point>> = FxHashMap::default();
+    let mut has_predecessor = FxHashSet::default();
+    for &(p, q) in &all_facts.cfg_edge {
+        successors.entry(p).or_insert(vec![]).push(q);
+        has_predecessor.insert(q);
+    }
+
+    let mut points: Vec<Point> = all_facts
+        .cfg_edge
+        .iter()
+        .flat_map(|&(p, q)| vec![p, q])
+        .collect();
+    points.sort_by_key(|&point| (tables.point_location(point), point));
+    points.dedup();
+
+    let roots: Vec<Point> = points
+        .iter()
+        .filter(|point| !has_predecessor.contains(point))
+        .cloned()
+        .collect();
+    let mut problems: Vec<_> = roots
+        .iter()
+        .skip(1)
+        .map(|&point| Problem::NoPredecessors { point })
+        .collect();
+
+    let mut reachable: FxHashSet<Point> = roots.iter().cloned().collect();
+    let mut stack = roots;
+    while let Some(p) = stack.pop() {
+        for &q in successors.get(&p).map(|v| &v[..]).unwrap_or(&[]) {
+            if reachable.insert(q) {
+                stack.push(q);
+            }
+        }
+    }
+
+    problems.extend(
+        points
+            .iter()
+            .filter(|&point| !reachable.contains(point))
+            .map(|&point| Problem::Unreachable { point }),
+    );
+    problems
+}
+
+fn count_duplicates<T: Eq + Hash>(rows: &[T]) -> usize {
+    let distinct: FxHashSet<&T> = rows.iter().collect();
+    rows.len() - distinct.len()
+}